@@ -1,8 +1,3 @@
-use std::env;
-use std::fs;
-
-mod intcode;
-
 #[derive(Debug)]
 enum Direction {
     Up,
@@ -11,14 +6,6 @@ enum Direction {
     Right,
 }
 
-fn tokenize(filepath: &String) -> Vec<i64> {
-    let contents = fs::read_to_string(filepath).expect("Error reading file");
-    return contents
-        .split(',')
-        .map(|input| input.parse().unwrap())
-        .collect();
-}
-
 fn get_direction(new_move: i64, last_move: &Direction) -> Direction {
     match last_move {
         Direction::Up => {
@@ -61,10 +48,10 @@ fn get_position(last_position: &(i64, i64), direction: &Direction) -> (i64, i64)
     }
 }
 
-fn get_output(output: Option<i64>) -> i64 {
-    match output {
-        Some(item) => item,
-        None => panic!("No output value"),
+fn get_output(state: intcode::RunState) -> i64 {
+    match state {
+        intcode::RunState::Output(item) => item,
+        state => panic!("Unexpected machine state {:?}", state),
     }
 }
 
@@ -81,20 +68,22 @@ fn get_current_color(pos: &(i64, i64), moves: &Vec<(i64, (i64, i64))>) -> i64 {
 
 fn get_colored_map(program: Vec<i64>) -> Vec<(i64, (i64, i64))> {
     let mut moves: Vec<(i64, (i64, i64))> = Vec::new();
-    let mut machine = intcode::create_new_machine(program);
+    let mut machine = intcode::Machine::new(program);
     let mut position: (i64, i64) = (0, 0);
     let mut direction: Direction = Direction::Up;
 
     moves.push((1, position));
 
-    while !machine.is_finished() {
+    loop {
         let input = get_current_color(&position, &moves);
 
         machine.push_input(input);
-        machine.execute_program();
 
-        let color = get_output(machine.pop_output());
-        let next_move = get_output(machine.pop_output());
+        let color = match machine.run_until_output() {
+            intcode::RunState::Halted => break,
+            state => get_output(state),
+        };
+        let next_move = get_output(machine.run_until_output());
 
         if is_unique(&position, &moves) {
             moves.push((color, position));
@@ -175,14 +164,14 @@ fn print(map: Vec<Vec<char>>) {
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let (args, patch) = intcode::patch::from_env();
     if args.len() < 2 {
         println!("Provide filename");
         return;
     }
     let filepath = &args[1];
 
-    let program = tokenize(filepath);
+    let program = patch.load_program(filepath);
     let map = get_colored_map(program);
     let scaled = scale(map);
     let printable = get_printable_map(scaled);
@@ -1,16 +1,3 @@
-use std::env;
-use std::fs;
-
-mod intcode;
-
-fn tokenize(filepath: &String) -> Vec<i64> {
-    let contents = fs::read_to_string(filepath).expect("Error reading file");
-    return contents
-        .split(',')
-        .map(|input| input.parse().unwrap())
-        .collect();
-}
-
 fn get_full_output(machine: &mut intcode::Machine) -> Vec<(i64, (i64, i64))> {
     let mut outputs: Vec<i64> = Vec::new();
     loop {
@@ -25,9 +12,11 @@ fn get_full_output(machine: &mut intcode::Machine) -> Vec<(i64, (i64, i64))> {
 
 fn solve(program: Vec<i64>) {
     let block_tile_id = 2;
-    let mut machine = intcode::create_new_machine(program);
+    let mut machine = intcode::Machine::new(program);
 
-    machine.execute_program();
+    if let intcode::RunState::Faulted(error) = machine.execute_program() {
+        panic!("{}", error);
+    }
 
     let tiles = get_full_output(&mut machine);
 
@@ -36,13 +25,13 @@ fn solve(program: Vec<i64>) {
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let (args, patch) = intcode::patch::from_env();
     if args.len() < 2 {
         println!("Provide filename");
         return;
     }
     let filepath = &args[1];
 
-    let program = tokenize(filepath);
+    let program = patch.load_program(filepath);
     solve(program);
 }
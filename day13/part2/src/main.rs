@@ -1,75 +1,68 @@
-use std::env;
-use std::fs;
-
-mod intcode;
-
-fn tokenize(filepath: &String) -> Vec<i64> {
-    let contents = fs::read_to_string(filepath).expect("Error reading file");
-    return contents
-        .split(',')
-        .map(|input| input.parse().unwrap())
-        .collect();
+fn get_output(state: intcode::RunState) -> i64 {
+    match state {
+        intcode::RunState::Output(item) => item,
+        state => panic!("Unexpected machine state {:?}", state),
+    }
 }
 
-fn get_full_output(machine: &mut intcode::Machine) -> Vec<(i64, (i64, i64))> {
-    let mut outputs: Vec<i64> = Vec::new();
-    loop {
-        match machine.pop_output() {
-            Some(item) => outputs.push(item),
-            None => break,
+fn get_joystick(ball: Option<(i64, i64)>, paddle: Option<(i64, i64)>) -> i64 {
+    match (ball, paddle) {
+        (Some(ball), Some(paddle)) => {
+            if ball.0 < paddle.0 {
+                -1
+            } else if ball.0 > paddle.0 {
+                1
+            } else {
+                0
+            }
         }
+        _ => 0,
     }
-
-    return outputs.as_slice().chunks(3).map(|c| (c[2], (c[0], c[1]))).collect();
 }
 
-fn solve(mut program: Vec<i64>) {
-    program[0] = 2;
-    let mut machine = intcode::create_new_machine(program);
+fn solve(program: Vec<i64>) {
+    let mut machine = intcode::Machine::new(program);
     let mut score = 0;
     let mut ball: Option<(i64, i64)> = None;
     let mut paddle: Option<(i64, i64)> = None;
-    let mut input: i64 = 0;
 
-    while !machine.is_finished() {
-        machine.execute_program();
-
-        let output = get_full_output(&mut machine);
+    loop {
+        match machine.run_until_output() {
+            intcode::RunState::Output(x) => {
+                let y = get_output(machine.run_until_output());
+                let tile = get_output(machine.run_until_output());
 
-        for tile in output {
-            if (tile.1).0 == -1 && (tile.1).1 == 0 {
-                score = tile.0;
-            } else if tile.0 == 3 {
-                paddle = Some(((tile.1).0, (tile.1).1));
-            } else if tile.0 == 4 {
-                ball = Some(((tile.1).0, (tile.1).1));
-            }
-    
-            if ball != None && paddle != None {
-                if ball.unwrap().0 < paddle.unwrap().0 {
-                    input = -1;
-                } else if ball.unwrap().0 > paddle.unwrap().0 {
-                    input = 1;
-                } else {
-                    input = 0;
+                if x == -1 && y == 0 {
+                    score = tile;
+                } else if tile == 3 {
+                    paddle = Some((x, y));
+                } else if tile == 4 {
+                    ball = Some((x, y));
                 }
             }
+            intcode::RunState::NeedsInput => machine.push_input(get_joystick(ball, paddle)),
+            intcode::RunState::Halted => break,
+            intcode::RunState::Faulted(error) => panic!("{}", error),
+            intcode::RunState::BudgetExhausted => panic!("budget exhausted"),
         }
-
-        machine.push_input(input);
     }
 
     println!("{}", score);
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let (args, options) = intcode::patch::from_env();
     if args.len() < 2 {
         println!("Provide filename");
         return;
     }
     let filepath = &args[1];
 
-    let program = tokenize(filepath);
+    let mut patch = intcode::patch::Patch::new();
+    // Two quarters for free play.
+    patch.poke(0, &[2]);
+    patch.extend(options);
+
+    let program = patch.load_program(filepath);
     solve(program);
 }
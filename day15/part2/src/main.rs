@@ -1,9 +1,5 @@
 use std::collections::HashMap;
 use std::collections::VecDeque;
-use std::env;
-use std::fs;
-
-mod intcode;
 
 type Point = (i64, i64);
 
@@ -14,18 +10,10 @@ enum Tile {
     Oxygen,
 }
 
-fn tokenize(filepath: &String) -> Vec<i64> {
-    let contents = fs::read_to_string(filepath).expect("Error reading file");
-    return contents
-        .split(',')
-        .map(|input| input.parse().unwrap())
-        .collect();
-}
-
-fn unwrap_output(output: Option<i64>) -> i64 {
-    match output {
-        Some(item) => item,
-        None => panic!("Expected output value"),
+fn unwrap_output(state: intcode::RunState) -> i64 {
+    match state {
+        intcode::RunState::Output(item) => item,
+        state => panic!("Unexpected machine state {:?}", state),
     }
 }
 
@@ -48,52 +36,33 @@ fn get_new_position(direction: i64, old_position: &Point) -> Point {
     }
 }
 
-fn get_reverse_dir(direction: i64) -> i64 {
-    match direction {
-        1 => 2,
-        2 => 1,
-        3 => 4,
-        4 => 3,
-        _ => panic!("Unexpected direction"),
-    }
-}
-
-fn visit(machine: &mut intcode::Machine, map: &mut HashMap<Point, Tile>, pos: Point) {
+fn visit(machine: &intcode::Machine, map: &mut HashMap<Point, Tile>, pos: Point) {
     for dir in 1..5 {
         let new_pos = get_new_position(dir, &pos);
 
-        if !map.contains_key(&new_pos) {
-            machine.push_input(dir);
-            machine.execute_program();
-
-            let tile = get_tile_type(unwrap_output(machine.pop_output()));
-            map.insert(new_pos, tile);
-
-            if tile != Tile::Wall {
-                visit(machine, map, new_pos);
+        if map.contains_key(&new_pos) {
+            continue;
+        }
 
-                let reversed = get_reverse_dir(dir);
+        let mut branch = machine.clone();
+        branch.push_input(dir);
 
-                machine.push_input(reversed);
-                machine.execute_program();
+        let tile = get_tile_type(unwrap_output(branch.run_until_output()));
+        map.insert(new_pos, tile);
 
-                let reversed_tile = get_tile_type(unwrap_output(machine.pop_output()));
-                let reversed_pos = get_new_position(dir, &pos);
-                if !map.contains_key(&reversed_pos) {
-                    map.insert(reversed_pos, reversed_tile);
-                }
-            }
+        if tile != Tile::Wall {
+            visit(&branch, map, new_pos);
         }
     }
 }
 
 fn generate_map(program: Vec<i64>) -> HashMap<Point, Tile> {
-    let mut machine = intcode::create_new_machine(program);
+    let machine = intcode::Machine::new(program);
     let mut map: HashMap<Point, Tile> = HashMap::new();
 
     map.insert((0, 0), Tile::Empty);
 
-    visit(&mut machine, &mut map, (0, 0));
+    visit(&machine, &mut map, (0, 0));
 
     return map;
 }
@@ -154,13 +123,13 @@ fn solve(map: HashMap<Point, Tile>) {
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let (args, patch) = intcode::patch::from_env();
     if args.len() < 2 {
         println!("Provide filename");
         return;
     }
     let filepath = &args[1];
-    let input = tokenize(filepath);
+    let input = patch.load_program(filepath);
     let map = generate_map(input);
     solve(map);
 }
@@ -0,0 +1,50 @@
+use std::fs;
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_part1"))
+        .args(args)
+        .output()
+        .expect("Error running part1")
+}
+
+fn puzzle_input() -> String {
+    format!("{}/../input/input", env!("CARGO_MANIFEST_DIR"))
+}
+
+#[test]
+fn readme_examples() {
+    let examples = [
+        ("1,9,10,3,2,3,11,0,99,30,40,50", "3500"),
+        ("1,0,0,0,99", "2"),
+        ("2,3,0,3,99", "2"),
+        ("2,4,4,5,99,0", "2"),
+        ("1,1,1,4,99,5,6,0,99", "30"),
+    ];
+    let filepath = std::env::temp_dir().join(format!("day2-part1-{}", std::process::id()));
+    let filepath = filepath.to_str().unwrap();
+
+    for (program, position_0) in examples.iter() {
+        fs::write(filepath, format!("{}\n", program)).unwrap();
+        let output = run(&[filepath]);
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("{}\n", position_0),
+            "{}",
+            program
+        );
+    }
+    fs::remove_file(filepath).unwrap();
+}
+
+#[test]
+fn puzzle_input_in_1202_program_alarm_state() {
+    let output = run(&[&puzzle_input(), "--poke", "1=12,2"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "4330636\n");
+
+    let output = run(&[&puzzle_input(), "--poke", "1000=1"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("poke at 1000 is outside the program"));
+}
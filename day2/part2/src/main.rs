@@ -1,64 +1,22 @@
-use std::env;
-use std::fs;
+fn solve(input: &[i64]) {
+    let mut symbolic = intcode::symbolic::Symbolic::new(input);
+    symbolic.add_symbol(1, "noun", 0..=99);
+    symbolic.add_symbol(2, "verb", 0..=99);
 
-fn tokenize(filepath: &String) -> Vec<i32> {
-    let contents = fs::read_to_string(filepath).expect("Error reading file");
-    return contents
-        .split(',')
-        .map(|input| input.parse().unwrap())
-        .collect();
-}
-
-fn execute_operation(op: i32, pos: usize, input: &mut Vec<i32>) {
-    let lhs = input[input[pos + 1] as usize];
-    let rhs = input[input[pos + 2] as usize];
-    let dest = input[pos + 3] as usize;
-
-    match op {
-        1 => {
-            input[dest] = lhs + rhs;
-        }
-        2 => {
-            input[dest] = lhs * rhs;
-        }
-        _ => {
-            panic!("Unexpected operation");
-        }
-    }
-}
-
-fn execute_program(input: &mut Vec<i32>) {
-    for i in (16..input.len()).step_by(4) {
-        if input[i] == 99 {
-            break;
-        }
-        execute_operation(input[i], i, input);
-    }
-}
-
-fn bruteforce(input: &Vec<i32>) {
-    for i in 0..100 {
-        for j in 0..100 {
-            let mut vec = input.clone();
-            vec[1] = i;
-            vec[2] = j;
-            execute_program(&mut vec);
-            if vec[0] == 19690720 {
-                println!("{}", (100 * vec[1]) + vec[2]);
-                break;
-            }
-        }
+    match symbolic.solve(0, 19690720) {
+        Some(values) => println!("{}", (100 * values[0]) + values[1]),
+        None => println!("No noun and verb produce 19690720"),
     }
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let (args, patch) = intcode::patch::from_env();
     if args.len() < 2 {
         println!("Provide filename");
         return;
     }
     let filepath = &args[1];
-    let mut input = tokenize(filepath);
+    let input = patch.load_program(filepath);
 
-    bruteforce(&mut input);
+    solve(&input);
 }
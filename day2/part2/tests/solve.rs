@@ -0,0 +1,26 @@
+use std::fs;
+use std::process::Command;
+
+fn run(filepath: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_part2"))
+        .arg(filepath)
+        .output()
+        .expect("Error running part2");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn puzzle_input() {
+    let filepath = format!("{}/../input/input", env!("CARGO_MANIFEST_DIR"));
+    assert_eq!(run(&filepath), "6086\n");
+}
+
+#[test]
+fn programs_without_a_solution() {
+    let filepath = std::env::temp_dir().join(format!("day2-part2-{}", std::process::id()));
+    let filepath = filepath.to_str().unwrap();
+
+    fs::write(filepath, "1,9,10,3,2,3,11,0,99,30,40,50\n").unwrap();
+    assert_eq!(run(filepath), "No noun and verb produce 19690720\n");
+    fs::remove_file(filepath).unwrap();
+}
@@ -1,18 +1,5 @@
-use std::env;
-use std::fs;
-
-mod intcode_machine;
-
-fn tokenize(filepath: &String) -> Vec<i64> {
-    let contents = fs::read_to_string(filepath).expect("Error reading file");
-    return contents
-        .split(',')
-        .map(|input| input.parse().unwrap())
-        .collect();
-}
-
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let (args, patch) = intcode::patch::from_env();
     if args.len() < 3 {
         println!("Provide filename and input");
         return;
@@ -20,11 +7,13 @@ fn main() {
     let filepath = &args[1];
     let input: i64 = args[2].parse().unwrap();
 
-    let program = tokenize(filepath);
-    let mut machine = intcode_machine::create_new_machine(program);
+    let program = patch.load_program(filepath);
+    let mut machine = intcode::Machine::new(program);
 
     machine.push_input(input);
-    machine.execute_program();
 
-    println!("{}", machine.pop_output().unwrap());
+    match machine.execute_program() {
+        intcode::RunState::Halted => println!("{}", machine.pop_output().unwrap()),
+        state => println!("Unexpected machine state {:?}", state),
+    }
 }
@@ -0,0 +1,143 @@
+//! The interpreter loop as it was before the decode cache: every step decodes
+//! the instruction again and collects its modes into a fresh `Vec`. Kept only
+//! as a baseline for the benchmarks, so faults simply stop the machine.
+
+use std::collections::VecDeque;
+
+use intcode::decode;
+use intcode::memory::{DenseMemory, Memory};
+
+fn get_addressing(input: i64, op_size: usize) -> Option<Vec<i64>> {
+    let mut temp = input;
+    let mut addressing: Vec<i64> = Vec::new();
+
+    for _ in 0..op_size - 1 {
+        let mode = temp % 10;
+        if !(0..=2).contains(&mode) {
+            return None;
+        }
+
+        addressing.push(mode);
+        temp /= 10;
+    }
+
+    Some(addressing)
+}
+
+pub struct Machine {
+    memory: Box<dyn Memory>,
+    ip: usize,
+    relative_base: i64,
+    input: VecDeque<i64>,
+}
+
+impl Clone for Machine {
+    fn clone(&self) -> Machine {
+        Machine {
+            memory: self.memory.clone_box(),
+            ip: self.ip,
+            relative_base: self.relative_base,
+            input: self.input.clone(),
+        }
+    }
+}
+
+impl Machine {
+    pub fn new(program: Vec<i64>) -> Machine {
+        Machine {
+            memory: Box::new(DenseMemory::new(program)),
+            ip: 0,
+            relative_base: 0,
+            input: VecDeque::new(),
+        }
+    }
+
+    pub fn push_input(&mut self, input: i64) {
+        self.input.push_back(input);
+    }
+
+    fn get_address(&self, addressing: i64, pos: usize) -> Option<usize> {
+        let address = match addressing {
+            0 => self.memory.read(pos),
+            1 => return Some(pos),
+            _ => self.relative_base.checked_add(self.memory.read(pos))?,
+        };
+        if address < 0 {
+            return None;
+        }
+        Some(address as usize)
+    }
+
+    fn get_value(&self, addressing: i64, pos: usize) -> Option<i64> {
+        Some(self.memory.read(self.get_address(addressing, pos)?))
+    }
+
+    fn write_to_memory(&mut self, addressing: i64, pos: usize, value: i64) -> Option<()> {
+        if addressing == 1 {
+            return None;
+        }
+        let address = self.get_address(addressing, pos)?;
+        if self.memory.write(address, value) {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Runs to the next output. `None` means the machine halted, faulted or
+    /// needs input.
+    pub fn run_until_output(&mut self) -> Option<i64> {
+        loop {
+            if self.ip >= self.memory.extent() {
+                return None;
+            }
+            let opcode = self.memory.read(self.ip);
+            let op = opcode % 100;
+            let op_size = decode::get_operation_size(op)?;
+            if op == decode::HALT {
+                return None;
+            }
+            let addressing = get_addressing(opcode / 100, op_size)?;
+            let ip = self.ip;
+
+            match op {
+                1 | 2 | 7 | 8 => {
+                    let lhs = self.get_value(addressing[0], ip + 1)?;
+                    let rhs = self.get_value(addressing[1], ip + 2)?;
+                    let value = match op {
+                        1 => lhs + rhs,
+                        2 => lhs * rhs,
+                        7 => (lhs < rhs) as i64,
+                        _ => (lhs == rhs) as i64,
+                    };
+                    self.write_to_memory(addressing[2], ip + 3, value)?;
+                }
+                3 => {
+                    let value = self.input.pop_front()?;
+                    self.write_to_memory(addressing[0], ip + 1, value)?;
+                }
+                4 => {
+                    let value = self.get_value(addressing[0], ip + 1)?;
+                    self.ip += op_size;
+                    return Some(value);
+                }
+                5 | 6 => {
+                    let condition = self.get_value(addressing[0], ip + 1)?;
+                    let dest = self.get_value(addressing[1], ip + 2)?;
+                    if (condition != 0) == (op == 5) {
+                        if dest < 0 {
+                            return None;
+                        }
+                        self.ip = dest as usize;
+                        continue;
+                    }
+                }
+                _ => {
+                    let adjustment = self.get_value(addressing[0], ip + 1)?;
+                    self.relative_base = self.relative_base.checked_add(adjustment)?;
+                }
+            }
+            self.ip += op_size;
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{HashSet, VecDeque};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use intcode::amplifier::{optimize, permutations};
+use intcode::{Machine, RunState};
+
+mod allocating;
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::day_input;
+
+/// What the workloads need from an interpreter, so the same workload can run
+/// on the library machine and on the allocating baseline.
+trait Interpreter: Clone {
+    fn push_input(&mut self, input: i64);
+
+    /// Runs to the next output, or returns `None` once the machine stops.
+    fn next_output(&mut self) -> Option<i64>;
+}
+
+impl Interpreter for Machine {
+    fn push_input(&mut self, input: i64) {
+        Machine::push_input(self, input)
+    }
+
+    fn next_output(&mut self) -> Option<i64> {
+        match self.run_until_output() {
+            RunState::Output(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Interpreter for allocating::Machine {
+    fn push_input(&mut self, input: i64) {
+        allocating::Machine::push_input(self, input)
+    }
+
+    fn next_output(&mut self) -> Option<i64> {
+        self.run_until_output()
+    }
+}
+
+fn cached(program: &[i64]) -> Machine {
+    Machine::new(program.to_vec())
+}
+
+fn uncached(program: &[i64]) -> Machine {
+    let mut machine = Machine::new(program.to_vec());
+    machine.set_decode_cache(false);
+    machine
+}
+
+fn baseline(program: &[i64]) -> allocating::Machine {
+    allocating::Machine::new(program.to_vec())
+}
+
+/// Day 7 part 2 on the allocating baseline, which `intcode::amplifier` cannot
+/// run: every phase permutation through the feedback loop.
+fn baseline_amplifier_search(program: &[i64]) -> i64 {
+    let mut best = i64::MIN;
+    for phases in permutations(&[5, 6, 7, 8, 9], 5) {
+        let mut amps: Vec<allocating::Machine> = phases
+            .iter()
+            .map(|phase| {
+                let mut amp = baseline(program);
+                amp.push_input(*phase);
+                amp
+            })
+            .collect();
+
+        let mut signal = 0;
+        'feedback: loop {
+            for amp in amps.iter_mut() {
+                amp.push_input(signal);
+                match amp.run_until_output() {
+                    Some(value) => signal = value,
+                    None => break 'feedback,
+                }
+            }
+        }
+        best = best.max(signal);
+    }
+    best
+}
+
+/// Day 9 part 2: the BOOST sensor mode.
+fn boost<I: Interpreter>(mut machine: I) -> i64 {
+    machine.push_input(2);
+    machine.next_output().unwrap()
+}
+
+/// Day 15: breadth-first flood fill of the whole maze, cloning a droid per move.
+fn flood_fill<I: Interpreter>(droid: I) -> usize {
+    let moves = [(1, (0, -1)), (2, (0, 1)), (3, (-1, 0)), (4, (1, 0))];
+    let mut visited: HashSet<(i64, i64)> = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    visited.insert((0, 0));
+    queue.push_back(((0, 0), droid));
+    while let Some(((x, y), droid)) = queue.pop_front() {
+        for (command, (dx, dy)) in moves.iter() {
+            let position = (x + dx, y + dy);
+            if visited.contains(&position) {
+                continue;
+            }
+
+            let mut next = droid.clone();
+            next.push_input(*command);
+            if let Some(status) = next.next_output() {
+                if status != 0 {
+                    visited.insert(position);
+                    queue.push_back((position, next));
+                }
+            }
+        }
+    }
+    visited.len()
+}
+
+fn interpreter(c: &mut Criterion) {
+    let program = day_input(7);
+    let mut group = c.benchmark_group("day7 amplifier search");
+    group.sample_size(10);
+    group.bench_function("intcode::amplifier", |b| {
+        b.iter(|| optimize(&program, &[5, 6, 7, 8, 9], 5, true).unwrap())
+    });
+    group.bench_function("allocating decoder", |b| {
+        b.iter(|| baseline_amplifier_search(&program))
+    });
+    group.finish();
+
+    let program = day_input(9);
+    let mut group = c.benchmark_group("day9 boost");
+    group.sample_size(10);
+    group.bench_function("decode cache", |b| b.iter(|| boost(cached(&program))));
+    group.bench_function("no decode cache", |b| b.iter(|| boost(uncached(&program))));
+    group.bench_function("allocating decoder", |b| {
+        b.iter(|| boost(baseline(&program)))
+    });
+    group.finish();
+
+    let program = day_input(15);
+    let mut group = c.benchmark_group("day15 flood fill");
+    group.sample_size(10);
+    group.bench_function("decode cache", |b| b.iter(|| flood_fill(cached(&program))));
+    group.bench_function("no decode cache", |b| {
+        b.iter(|| flood_fill(uncached(&program)))
+    });
+    group.bench_function("allocating decoder", |b| {
+        b.iter(|| flood_fill(baseline(&program)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, interpreter);
+criterion_main!(benches);
@@ -0,0 +1,23 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const DAYS: [u32; 7] = [2, 5, 7, 9, 11, 13, 15];
+
+fn main() {
+    let mut source = String::new();
+
+    for day in DAYS.iter() {
+        let filepath = format!("../../day{}/input/input", day);
+        println!("cargo:rerun-if-changed={}", filepath);
+
+        let program = intcode::tokenize(&filepath);
+        source.push_str(&intcode::compiler::compile(
+            &program,
+            &format!("day{}", day),
+        ));
+    }
+
+    let output = Path::new(&env::var("OUT_DIR").unwrap()).join("compiled.rs");
+    fs::write(output, source).expect("Error writing file");
+}
@@ -0,0 +1,3 @@
+//! Every day's Intcode input compiled ahead of time by `intcode::compiler`.
+
+include!(concat!(env!("OUT_DIR"), "/compiled.rs"));
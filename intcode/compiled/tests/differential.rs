@@ -0,0 +1,112 @@
+use intcode::compiler::State;
+use intcode::{Machine, RunState};
+use intcode_compiled::{day11, day13, day15, day2, day5, day7, day9};
+
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+use common::{day_input, drive, joystick, robot, scripted, walk, Runner, Transcript};
+
+struct Compiled {
+    state: State,
+    run: fn(&mut State) -> RunState,
+}
+
+impl Runner for Compiled {
+    fn push_input(&mut self, input: i64) {
+        self.state.push_input(input)
+    }
+
+    fn run(&mut self) -> RunState {
+        (self.run)(&mut self.state)
+    }
+
+    fn pop_output(&mut self) -> Option<i64> {
+        self.state.pop_output()
+    }
+
+    fn read(&self, address: usize) -> i64 {
+        self.state.load(address)
+    }
+}
+
+type Program = (fn() -> State, fn(&mut State) -> RunState);
+
+fn compare<F: Fn() -> G, G: FnMut(&[i64]) -> Option<i64>>(
+    day: u32,
+    patches: &[(usize, i64)],
+    compiled: Program,
+    respond: F,
+) -> Transcript {
+    let mut program = day_input(day);
+    let (create, run) = compiled;
+    let mut state = create();
+    for (address, value) in patches {
+        program[*address] = *value;
+        state.poke(*address, *value).unwrap();
+    }
+
+    let expected = drive(Machine::new(program), respond());
+    let actual = drive(Compiled { state, run }, respond());
+    assert_eq!(actual, expected, "day {} patched with {:?}", day, patches);
+    expected
+}
+
+#[test]
+fn day2_matches_interpreter() {
+    for (noun, verb) in [(12, 2), (0, 0), (99, 99), (53, 35)].iter() {
+        let transcript = compare(
+            2,
+            &[(1, *noun), (2, *verb)],
+            (day2::state, day2::run),
+            || scripted(&[]),
+        );
+        assert_eq!(transcript.state, RunState::Halted);
+    }
+}
+
+#[test]
+fn day5_matches_interpreter() {
+    for input in [1, 5, 7, 8, 9].iter() {
+        compare(5, &[], (day5::state, day5::run), || scripted(&[*input]));
+    }
+}
+
+#[test]
+fn day7_matches_interpreter() {
+    for phase in 0..10 {
+        for signal in [0, 1, 54321].iter() {
+            compare(7, &[], (day7::state, day7::run), || {
+                scripted(&[phase, *signal])
+            });
+        }
+    }
+}
+
+#[test]
+fn day9_matches_interpreter() {
+    for input in [1, 2].iter() {
+        compare(9, &[], (day9::state, day9::run), || scripted(&[*input]));
+    }
+}
+
+#[test]
+fn day11_matches_interpreter() {
+    for start in [0, 1].iter() {
+        let transcript = compare(11, &[], (day11::state, day11::run), || robot(*start));
+        assert_eq!(transcript.state, RunState::Halted);
+    }
+}
+
+#[test]
+fn day13_matches_interpreter() {
+    compare(13, &[], (day13::state, day13::run), || scripted(&[]));
+
+    let transcript = compare(13, &[(0, 2)], (day13::state, day13::run), || joystick);
+    assert_eq!(transcript.state, RunState::Halted);
+}
+
+#[test]
+fn day15_matches_interpreter() {
+    compare(15, &[], (day15::state, day15::run), walk);
+}
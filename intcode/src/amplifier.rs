@@ -0,0 +1,136 @@
+//! Chains of amplifiers each running a copy of the same program, as in day 7.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::error::MachineError;
+use crate::machine::Machine;
+use crate::network::{Network, NetworkState, Schedule, Topology};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AmplifierError {
+    /// There were no phase settings, so no amplifiers to run.
+    NoAmplifiers,
+    /// Every amplifier halted, but the last one never sent a signal.
+    NoSignal,
+    Faulted {
+        amplifier: usize,
+        error: MachineError,
+    },
+    /// The chain stopped before every amplifier halted.
+    Stopped { state: NetworkState },
+}
+
+impl fmt::Display for AmplifierError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AmplifierError::NoAmplifiers => write!(f, "no phase settings"),
+            AmplifierError::NoSignal => write!(f, "amplifiers halted without a signal"),
+            AmplifierError::Faulted { amplifier, error } => {
+                write!(f, "amplifier {} faulted: {}", amplifier, error)
+            }
+            AmplifierError::Stopped { state } => {
+                write!(f, "amplifiers stopped before halting: {:?}", state)
+            }
+        }
+    }
+}
+
+impl Error for AmplifierError {}
+
+/// Runs one amplifier per phase setting, starting the chain with signal 0, and
+/// returns the last signal the final amplifier sends. With `feedback` the final
+/// amplifier also feeds the first until they all halt.
+pub fn amplify(program: &[i64], phases: &[i64], feedback: bool) -> Result<i64, AmplifierError> {
+    if phases.is_empty() {
+        return Err(AmplifierError::NoAmplifiers);
+    }
+    let amps: Vec<Machine> = phases
+        .iter()
+        .map(|phase| {
+            let mut amp = Machine::new(program.to_vec());
+            amp.push_input(*phase);
+            amp
+        })
+        .collect();
+    let topology = if feedback {
+        Topology::Ring
+    } else {
+        Topology::Pipeline
+    };
+    let mut network = Network::new(amps, topology);
+    network.push_input(0, 0);
+
+    match network.run(Schedule::RoundRobin) {
+        NetworkState::Halted => network
+            .output()
+            .last()
+            .cloned()
+            .ok_or(AmplifierError::NoSignal),
+        NetworkState::Faulted { machine, error } => Err(AmplifierError::Faulted {
+            amplifier: machine,
+            error,
+        }),
+        state => Err(AmplifierError::Stopped { state }),
+    }
+}
+
+fn extend_permutations(
+    items: &[i64],
+    length: usize,
+    used: &mut Vec<bool>,
+    current: &mut Vec<i64>,
+    result: &mut Vec<Vec<i64>>,
+) {
+    if current.len() == length {
+        result.push(current.clone());
+        return;
+    }
+    for index in 0..items.len() {
+        if used[index] {
+            continue;
+        }
+        used[index] = true;
+        current.push(items[index]);
+        extend_permutations(items, length, used, current, result);
+        current.pop();
+        used[index] = false;
+    }
+}
+
+/// Every ordered selection of `length` distinct items, in the order the items
+/// are given, starting with `items[..length]` itself.
+pub fn permutations(items: &[i64], length: usize) -> Vec<Vec<i64>> {
+    let mut result = Vec::new();
+    if length <= items.len() {
+        extend_permutations(
+            items,
+            length,
+            &mut vec![false; items.len()],
+            &mut Vec::new(),
+            &mut result,
+        );
+    }
+    result
+}
+
+/// Tries every chain of `length` amplifiers with distinct phases from
+/// `phase_set`, returning the first phase sequence giving the highest signal,
+/// or `None` if `phase_set` has fewer than `length` phases. Stops at the first
+/// chain that fails.
+pub fn optimize(
+    program: &[i64],
+    phase_set: &[i64],
+    length: usize,
+    feedback: bool,
+) -> Result<Option<(Vec<i64>, i64)>, AmplifierError> {
+    let mut best: Option<(Vec<i64>, i64)> = None;
+
+    for phases in permutations(phase_set, length) {
+        let signal = amplify(program, &phases, feedback)?;
+        if best.as_ref().is_none_or(|(_, max)| signal > *max) {
+            best = Some((phases, signal));
+        }
+    }
+    Ok(best)
+}
@@ -0,0 +1,54 @@
+//! Text I/O for programs that talk in ASCII.
+
+use std::fmt;
+
+/// A run of decoded output: ASCII text, or a value outside the ASCII range
+/// passed through as is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ascii {
+    Text(String),
+    Value(i64),
+}
+
+impl fmt::Display for Ascii {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ascii::Text(text) => write!(f, "{}", text),
+            Ascii::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+pub fn is_ascii(value: i64) -> bool {
+    (0..128).contains(&value)
+}
+
+pub fn encode(text: &str) -> Vec<i64> {
+    text.chars().map(|c| c as i64).collect()
+}
+
+/// Groups consecutive ASCII values into text.
+pub fn decode(values: &[i64]) -> Vec<Ascii> {
+    let mut result = Vec::new();
+    let mut text = String::new();
+
+    for value in values {
+        if is_ascii(*value) {
+            text.push(*value as u8 as char);
+            continue;
+        }
+        if !text.is_empty() {
+            result.push(Ascii::Text(text.split_off(0)));
+        }
+        result.push(Ascii::Value(*value));
+    }
+    if !text.is_empty() {
+        result.push(Ascii::Text(text));
+    }
+    result
+}
+
+/// Joins decoded output back into one string, writing raw values as numbers.
+pub fn format(parts: &[Ascii]) -> String {
+    parts.iter().map(|part| part.to_string()).collect()
+}
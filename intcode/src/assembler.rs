@@ -0,0 +1,395 @@
+//! Assembler for the listing format printed by the disassembler.
+//!
+//! Operands are `[x]` (position), `#x` (immediate) or `rb+x` / `rb-x` (relative),
+//! where `x` is a number, `label` or `label+n`. Lines may start with `label:` and
+//! with a numeric `address:`, which must match the assembled address. `db a, b, ...`
+//! emits raw values, `dz n` emits `n` zeroes and `;` starts a comment. `push`, `pop`,
+//! `call` and `ret` expand to relative-base stack operations.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::decode;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssemblerError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssemblerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AssemblerError {}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Number(i64),
+    Label(String, i64),
+}
+
+#[derive(Debug, Clone)]
+struct Operand {
+    mode: i64,
+    value: Value,
+}
+
+#[derive(Debug)]
+enum Item {
+    Instruction { op: i64, operands: Vec<Operand> },
+    Data(Vec<Value>),
+    Zero(usize),
+}
+
+impl Item {
+    fn size(&self) -> usize {
+        match self {
+            Item::Instruction { operands, .. } => operands.len() + 1,
+            Item::Data(values) => values.len(),
+            Item::Zero(count) => *count,
+        }
+    }
+}
+
+struct Statement {
+    line: usize,
+    item: Item,
+}
+
+fn error(line: usize, message: String) -> AssemblerError {
+    AssemblerError { line, message }
+}
+
+fn is_identifier(token: &str) -> bool {
+    let mut chars = token.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn parse_number(token: &str, line: usize) -> Result<i64, AssemblerError> {
+    token
+        .parse()
+        .map_err(|_| error(line, format!("invalid number `{}`", token)))
+}
+
+fn parse_value(token: &str, line: usize) -> Result<Value, AssemblerError> {
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(error(line, "missing value".to_string()));
+    }
+    if !token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return Ok(Value::Number(parse_number(token, line)?));
+    }
+
+    match token.find(['+', '-']) {
+        Some(pos) => {
+            let label = token[..pos].trim();
+            let offset = parse_number(&token[pos..].replace(' ', "").replace("+", ""), line)?;
+
+            if !is_identifier(label) {
+                return Err(error(line, format!("invalid label `{}`", label)));
+            }
+            Ok(Value::Label(label.to_string(), offset))
+        }
+        None => {
+            if !is_identifier(token) {
+                return Err(error(line, format!("invalid label `{}`", token)));
+            }
+            Ok(Value::Label(token.to_string(), 0))
+        }
+    }
+}
+
+fn parse_operand(token: &str, line: usize) -> Result<Operand, AssemblerError> {
+    let token = token.trim();
+
+    if token.starts_with('[') && token.ends_with(']') {
+        return Ok(Operand {
+            mode: 0,
+            value: parse_value(&token[1..token.len() - 1], line)?,
+        });
+    }
+    if let Some(value) = token.strip_prefix('#') {
+        return Ok(Operand {
+            mode: 1,
+            value: parse_value(value, line)?,
+        });
+    }
+    if let Some(offset) = token.strip_prefix("rb") {
+        let offset = offset.trim();
+        let value = if offset.is_empty() {
+            Value::Number(0)
+        } else if let Some(offset) = offset.strip_prefix('+') {
+            parse_value(offset, line)?
+        } else if offset.starts_with('-') {
+            Value::Number(parse_number(&offset.replace(' ', ""), line)?)
+        } else {
+            return Err(error(line, format!("invalid operand `{}`", token)));
+        };
+
+        return Ok(Operand { mode: 2, value });
+    }
+
+    Err(error(
+        line,
+        format!("operand `{}` needs a mode marker: [x], #x or rb+x", token),
+    ))
+}
+
+fn split_operands(text: &str) -> Vec<&str> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    text.split(',').map(|operand| operand.trim()).collect()
+}
+
+fn make_instruction(op: i64, operands: Vec<Operand>, line: usize) -> Result<Item, AssemblerError> {
+    let size = decode::get_operation_size(op).unwrap();
+    let mnemonic = decode::get_mnemonic(op).unwrap();
+
+    if operands.len() != size - 1 {
+        return Err(error(
+            line,
+            format!(
+                "`{}` takes {} operands, got {}",
+                mnemonic,
+                size - 1,
+                operands.len()
+            ),
+        ));
+    }
+    if let Some(param) = decode::get_write_parameter(op) {
+        if operands[param].mode == 1 {
+            return Err(error(
+                line,
+                format!("`{}` cannot write to an immediate operand", mnemonic),
+            ));
+        }
+    }
+
+    Ok(Item::Instruction { op, operands })
+}
+
+fn immediate(value: Value) -> Operand {
+    Operand { mode: 1, value }
+}
+
+fn relative(offset: i64) -> Operand {
+    Operand {
+        mode: 2,
+        value: Value::Number(offset),
+    }
+}
+
+/// Expands the relative-base stack helpers. The stack grows upwards from the
+/// relative base, so programs using them should start with `arb #stack`.
+fn expand_helper(
+    mnemonic: &str,
+    operands: &[&str],
+    address: usize,
+    line: usize,
+) -> Result<Option<Vec<Item>>, AssemblerError> {
+    let expect_one = || -> Result<Operand, AssemblerError> {
+        match operands {
+            [operand] => parse_operand(operand, line),
+            _ => Err(error(line, format!("`{}` takes 1 operand", mnemonic))),
+        }
+    };
+
+    let items = match mnemonic {
+        "push" => vec![
+            make_instruction(
+                1,
+                vec![expect_one()?, immediate(Value::Number(0)), relative(0)],
+                line,
+            )?,
+            make_instruction(9, vec![immediate(Value::Number(1))], line)?,
+        ],
+        "pop" => vec![
+            make_instruction(9, vec![immediate(Value::Number(-1))], line)?,
+            make_instruction(
+                1,
+                vec![relative(0), immediate(Value::Number(0)), expect_one()?],
+                line,
+            )?,
+        ],
+        "call" => {
+            let target = expect_one()?;
+            let return_address = (address + 4 + 2 + 3) as i64;
+
+            vec![
+                make_instruction(
+                    1,
+                    vec![
+                        immediate(Value::Number(return_address)),
+                        immediate(Value::Number(0)),
+                        relative(0),
+                    ],
+                    line,
+                )?,
+                make_instruction(9, vec![immediate(Value::Number(1))], line)?,
+                make_instruction(5, vec![immediate(Value::Number(1)), target], line)?,
+            ]
+        }
+        "ret" => {
+            if !operands.is_empty() {
+                return Err(error(line, "`ret` takes no operands".to_string()));
+            }
+            vec![
+                make_instruction(9, vec![immediate(Value::Number(-1))], line)?,
+                make_instruction(6, vec![immediate(Value::Number(0)), relative(0)], line)?,
+            ]
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(items))
+}
+
+fn parse_item(
+    mnemonic: &str,
+    rest: &str,
+    address: usize,
+    line: usize,
+) -> Result<Vec<Item>, AssemblerError> {
+    let operands = split_operands(rest);
+
+    if mnemonic == "db" {
+        let values = operands
+            .iter()
+            .map(|value| parse_value(value, line))
+            .collect::<Result<Vec<Value>, AssemblerError>>()?;
+        if values.is_empty() {
+            return Err(error(line, "`db` needs at least one value".to_string()));
+        }
+        return Ok(vec![Item::Data(values)]);
+    }
+    if mnemonic == "dz" {
+        return match operands.as_slice() {
+            [count] => match parse_number(count, line)? {
+                count if count >= 0 => Ok(vec![Item::Zero(count as usize)]),
+                _ => Err(error(line, "`dz` needs a non-negative count".to_string())),
+            },
+            _ => Err(error(line, "`dz` takes 1 operand".to_string())),
+        };
+    }
+    if let Some(items) = expand_helper(mnemonic, &operands, address, line)? {
+        return Ok(items);
+    }
+
+    match decode::get_operation(mnemonic) {
+        Some(op) => {
+            let operands = operands
+                .iter()
+                .map(|operand| parse_operand(operand, line))
+                .collect::<Result<Vec<Operand>, AssemblerError>>()?;
+            Ok(vec![make_instruction(op, operands, line)?])
+        }
+        None => Err(error(line, format!("unknown mnemonic `{}`", mnemonic))),
+    }
+}
+
+fn parse(source: &str) -> Result<(Vec<Statement>, HashMap<String, usize>), AssemblerError> {
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    let mut address: usize = 0;
+
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let mut text = match text.find(';') {
+            Some(pos) => &text[..pos],
+            None => text,
+        }
+        .trim();
+
+        while let Some(pos) = text.find(':') {
+            let prefix = text[..pos].trim();
+            if is_identifier(prefix) {
+                if labels.insert(prefix.to_string(), address).is_some() {
+                    return Err(error(line, format!("duplicate label `{}`", prefix)));
+                }
+            } else if parse_number(prefix, line)? != address as i64 {
+                return Err(error(
+                    line,
+                    format!(
+                        "address {} does not match assembled address {}",
+                        prefix, address
+                    ),
+                ));
+            }
+            text = text[pos + 1..].trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, rest) = match text.find(char::is_whitespace) {
+            Some(pos) => (&text[..pos], &text[pos..]),
+            None => (text, ""),
+        };
+        for item in parse_item(&mnemonic.to_lowercase(), rest, address, line)? {
+            address += item.size();
+            statements.push(Statement { line, item });
+        }
+    }
+
+    Ok((statements, labels))
+}
+
+fn resolve(
+    value: &Value,
+    labels: &HashMap<String, usize>,
+    line: usize,
+) -> Result<i64, AssemblerError> {
+    match value {
+        Value::Number(number) => Ok(*number),
+        Value::Label(label, offset) => match labels.get(label) {
+            Some(address) => Ok(*address as i64 + offset),
+            None => Err(error(line, format!("unknown label `{}`", label))),
+        },
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Vec<i64>, AssemblerError> {
+    let (statements, labels) = parse(source)?;
+    let mut program: Vec<i64> = Vec::new();
+
+    for statement in statements {
+        let line = statement.line;
+        match statement.item {
+            Item::Instruction { op, operands } => {
+                let mut value = op;
+                let mut factor = 100;
+                for operand in operands.iter() {
+                    value += operand.mode * factor;
+                    factor *= 10;
+                }
+                program.push(value);
+                for operand in operands.iter() {
+                    program.push(resolve(&operand.value, &labels, line)?);
+                }
+            }
+            Item::Data(values) => {
+                for value in values.iter() {
+                    program.push(resolve(value, &labels, line)?);
+                }
+            }
+            Item::Zero(count) => program.resize(program.len() + count, 0),
+        }
+    }
+
+    Ok(program)
+}
+
+pub fn format_program(program: &[i64]) -> String {
+    let values: Vec<String> = program.iter().map(|value| value.to_string()).collect();
+    values.join(",")
+}
@@ -0,0 +1,39 @@
+use std::io::{self, BufRead, Write};
+
+fn main() {
+    let (args, patch) = intcode::patch::from_env();
+    if args.len() < 2 {
+        println!("Provide filename");
+        return;
+    }
+    let filepath = &args[1];
+    let program = patch.load_program(filepath);
+    let mut machine = intcode::Machine::new(program);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        let state = machine.execute_program();
+        for part in machine.pop_ascii() {
+            match part {
+                intcode::ascii::Ascii::Text(text) => print!("{}", text),
+                intcode::ascii::Ascii::Value(value) => println!("{}", value),
+            }
+        }
+        io::stdout().flush().expect("Error writing output");
+
+        match state {
+            intcode::RunState::NeedsInput => match lines.next() {
+                Some(line) => {
+                    machine.push_ascii(&format!("{}\n", line.expect("Error reading input")))
+                }
+                None => return,
+            },
+            intcode::RunState::Halted => return,
+            state => {
+                eprintln!("{:?}", state);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use std::env;
+use std::fs;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        println!("Provide filename");
+        return;
+    }
+    let filepath = &args[1];
+    let source = fs::read_to_string(filepath).expect("Error reading file");
+
+    let program = match intcode::assembler::assemble(&source) {
+        Ok(program) => intcode::assembler::format_program(&program),
+        Err(error) => {
+            eprintln!("{}: {}", filepath, error);
+            std::process::exit(1);
+        }
+    };
+
+    match args.get(2) {
+        Some(output) => fs::write(output, program).expect("Error writing file"),
+        None => print!("{}", program),
+    }
+}
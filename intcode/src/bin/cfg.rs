@@ -0,0 +1,13 @@
+use intcode::cfg::Cfg;
+
+fn main() {
+    let (args, patch) = intcode::patch::from_env();
+    if args.len() < 2 {
+        println!("Provide filename");
+        return;
+    }
+    let filepath = &args[1];
+    let program = patch.load_program(filepath);
+
+    print!("{}", Cfg::analyze(&program).to_dot());
+}
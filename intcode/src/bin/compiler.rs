@@ -0,0 +1,19 @@
+use std::fs;
+
+fn main() {
+    let (args, patch) = intcode::patch::from_env();
+    if args.len() < 2 {
+        println!("Provide filename");
+        return;
+    }
+    let filepath = &args[1];
+    let program = patch.load_program(filepath);
+    let name = args.get(2).map(String::as_str).unwrap_or("program");
+
+    let source = intcode::compiler::compile(&program, name);
+
+    match args.get(3) {
+        Some(output) => fs::write(output, source).expect("Error writing file"),
+        None => print!("{}", source),
+    }
+}
@@ -0,0 +1,24 @@
+use std::io;
+
+fn main() {
+    let (args, patch) = intcode::patch::from_env();
+    if args.len() < 2 {
+        println!("Provide filename");
+        return;
+    }
+    let filepath = &args[1];
+    let program = patch.load_program(filepath);
+    let mut machine = intcode::Machine::new(program);
+
+    for arg in &args[2..] {
+        machine.push_input(arg.parse().expect("Invalid input value"));
+    }
+
+    let mut debugger = intcode::debugger::Debugger::new(machine);
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    debugger
+        .run(stdin.lock(), stdout.lock())
+        .expect("Error accessing terminal");
+}
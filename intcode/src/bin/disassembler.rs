@@ -0,0 +1,13 @@
+fn main() {
+    let (args, patch) = intcode::patch::from_env();
+    if args.len() < 2 {
+        println!("Provide filename");
+        return;
+    }
+    let filepath = &args[1];
+    let program = patch.load_program(filepath);
+
+    for line in intcode::disassembler::disassemble(&program) {
+        println!("{}", line);
+    }
+}
@@ -0,0 +1,44 @@
+use std::fs;
+
+use intcode::patch::{exit_with, parse};
+use intcode::profile::Profile;
+
+const USAGE: &str = "\
+Usage:
+    profile <program> [--folded <file>] [--top N] [--poke A=V] [--patch FILE] [inputs...]";
+
+fn main() {
+    let (args, patch) = intcode::patch::from_env();
+    if args.len() < 2 {
+        exit_with(USAGE);
+    }
+    let program = patch.load_program(&args[1]);
+    let mut machine = intcode::Machine::new(program.clone());
+    let mut folded: Option<&String> = None;
+    let mut top = 10;
+    let mut options = args[2..].iter();
+
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--folded" => folded = Some(options.next().unwrap_or_else(|| exit_with(USAGE))),
+            "--top" => top = parse(options.next(), option),
+            _ => machine.push_input(parse(Some(option), "input")),
+        }
+    }
+    machine.set_profile(Profile::new());
+
+    let state = machine.execute_program();
+    while let Some(output) = machine.pop_output() {
+        println!("{}", output);
+    }
+    eprintln!("{:?}", state);
+
+    let profile = machine.take_profile().unwrap();
+    print!("{}", profile.summary(top));
+    print!("{}", profile.annotate(&program));
+    if let Some(filepath) = folded {
+        if let Err(error) = fs::write(filepath, profile.folded()) {
+            exit_with(&format!("{}: {}", filepath, error));
+        }
+    }
+}
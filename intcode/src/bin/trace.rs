@@ -0,0 +1,103 @@
+use std::process;
+
+use intcode::patch::{exit_with, parse, Patch};
+use intcode::trace::{Divergence, Filter, TraceRecord};
+
+const USAGE: &str = "\
+Usage:
+    trace record <program> <trace> [--poke A=V] [--patch FILE] [inputs...]
+    trace filter <trace> [--ip N] [--op N] [--address N] [--from STEP] [--to STEP]
+    trace diff <trace> <trace>";
+
+fn read(filepath: &str) -> Vec<TraceRecord> {
+    intcode::trace::read_trace(filepath)
+        .unwrap_or_else(|error| exit_with(&format!("{}: {}", filepath, error)))
+}
+
+fn record(args: &[String], patch: &Patch) {
+    if args.len() < 2 {
+        exit_with(USAGE);
+    }
+    let program = patch.load_program(&args[0]);
+    let tracer = intcode::trace::Tracer::create(&args[1])
+        .unwrap_or_else(|error| exit_with(&format!("{}: {}", args[1], error)));
+    let mut machine = intcode::Machine::new(program);
+
+    for arg in &args[2..] {
+        machine.push_input(parse(Some(arg), "input"));
+    }
+    machine.set_tracer(tracer);
+
+    let state = machine.execute_program();
+    while let Some(output) = machine.pop_output() {
+        println!("{}", output);
+    }
+    eprintln!("{:?}", state);
+
+    if let Err(error) = machine.take_tracer().unwrap().finish() {
+        exit_with(&format!("{}: {}", args[1], error));
+    }
+}
+
+fn filter(args: &[String]) {
+    if args.is_empty() {
+        exit_with(USAGE);
+    }
+    let mut filter = Filter::default();
+    let mut options = args[1..].iter();
+
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--ip" => filter.ip = Some(parse(options.next(), option)),
+            "--op" => filter.op = Some(parse(options.next(), option)),
+            "--address" => filter.address = Some(parse(options.next(), option)),
+            "--from" => filter.from = Some(parse(options.next(), option)),
+            "--to" => filter.to = Some(parse(options.next(), option)),
+            _ => exit_with(USAGE),
+        }
+    }
+
+    for record in read(&args[0])
+        .iter()
+        .filter(|record| filter.matches(record))
+    {
+        println!("{}", serde_json::to_string(record).unwrap());
+    }
+}
+
+fn diff(args: &[String]) {
+    if args.len() < 2 {
+        exit_with(USAGE);
+    }
+    let lhs = read(&args[0]);
+    let rhs = read(&args[1]);
+
+    match intcode::trace::find_divergence(&lhs, &rhs) {
+        None => println!("traces are identical ({} steps)", lhs.len()),
+        Some(Divergence::Record(left, right)) => {
+            println!("traces diverge at step {}", left.step);
+            println!("< {}", serde_json::to_string(&left).unwrap());
+            println!("> {}", serde_json::to_string(&right).unwrap());
+            process::exit(1);
+        }
+        Some(Divergence::Ended { step, longer }) => {
+            println!("one trace ends at step {}, the other continues with", step);
+            println!("  {}", serde_json::to_string(&longer).unwrap());
+            process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let (args, patch) = intcode::patch::from_env();
+    if args.len() < 2 {
+        exit_with(USAGE);
+    }
+
+    match args[1].as_str() {
+        "record" => record(&args[2..], &patch),
+        "filter" => filter(&args[2..]),
+        "diff" => diff(&args[2..]),
+        _ => exit_with(USAGE),
+    }
+}
@@ -0,0 +1,506 @@
+//! Control-flow graph of an Intcode program, built statically from the code
+//! the disassembler finds.
+//!
+//! Jumps with immediate targets give the edges. An unconditional jump is taken
+//! to be a call when its block first stores the address just after it, and a
+//! return when it jumps through a relative operand, as in `jz #0, rb+0`.
+//! Only position-mode writes can be resolved, so self-modification through
+//! relative or computed addresses goes unnoticed.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Write;
+
+use crate::decode::{self, Instruction};
+use crate::disassembler::{self, Line};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind {
+    Fallthrough,
+    Jump,
+    Call,
+    /// From a calling block to where execution resumes once the call returns.
+    Return,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// How control leaves a block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Exit {
+    Halt,
+    /// Runs on into the next block.
+    Fallthrough,
+    Jump,
+    /// A conditional jump, to its target or the next block.
+    Branch,
+    Call,
+    Return,
+    /// A jump whose target is only known at run time.
+    Indirect,
+    /// Runs on into cells that are not code.
+    IntoData,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub start: usize,
+    /// One past the last cell of the block.
+    pub end: usize,
+    pub lines: Vec<Line>,
+    pub exit: Exit,
+    pub reachable: bool,
+    /// Some instruction writes into the block's code.
+    pub self_modified: bool,
+}
+
+impl Block {
+    /// Address of the block's last instruction.
+    pub fn last(&self) -> usize {
+        match self.lines.last() {
+            Some(Line::Instruction { address, .. }) => *address,
+            _ => self.start,
+        }
+    }
+}
+
+/// A position-mode write into a cell of a decoded instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodeWrite {
+    /// Address of the writing instruction.
+    pub writer: usize,
+    pub address: usize,
+    /// Start of the instruction written into, or the cell a block runs into.
+    pub instruction: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subroutine {
+    pub entry: usize,
+    /// Frame size set up by an `arb #n` at the entry.
+    pub frame: Option<i64>,
+    /// Addresses of the jumps that call it.
+    pub callers: Vec<usize>,
+    /// Addresses of the jumps it returns with.
+    pub returns: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cfg {
+    pub blocks: Vec<Block>,
+    pub edges: Vec<Edge>,
+    pub subroutines: Vec<Subroutine>,
+    pub writes: Vec<CodeWrite>,
+}
+
+fn operands<'a>(program: &'a [i64], address: usize, instruction: &Instruction) -> &'a [i64] {
+    &program[address + 1..address + instruction.size]
+}
+
+fn is_jump(instruction: &Instruction) -> bool {
+    instruction.op == 5 || instruction.op == 6
+}
+
+/// A jump whose condition is an immediate that never lets it jump.
+fn is_never_taken(instruction: &Instruction, operands: &[i64]) -> bool {
+    is_jump(instruction)
+        && instruction.addressing[0] == 1
+        && (operands[0] != 0) != (instruction.op == 5)
+}
+
+fn is_return(instruction: &Instruction, operands: &[i64]) -> bool {
+    disassembler::is_unconditional_jump(instruction, operands) && instruction.addressing[1] == 2
+}
+
+fn find_leaders(program: &[i64], code: &BTreeMap<usize, Instruction>) -> BTreeSet<usize> {
+    let mut leaders: BTreeSet<usize> = BTreeSet::new();
+    let mut expected = None;
+
+    for (address, instruction) in code {
+        if expected != Some(*address) {
+            leaders.insert(*address);
+        }
+        if let Some(target) =
+            disassembler::get_jump_target(instruction, operands(program, *address, instruction))
+        {
+            leaders.insert(target);
+        }
+        if instruction.op == decode::HALT || is_jump(instruction) {
+            leaders.insert(address + instruction.size);
+        }
+        expected = Some(address + instruction.size);
+    }
+
+    leaders
+}
+
+fn find_blocks(program: &[i64], code: &BTreeMap<usize, Instruction>) -> Vec<Block> {
+    let leaders = find_leaders(program, code);
+    let mut blocks: Vec<Block> = Vec::new();
+
+    for (address, instruction) in code {
+        let extends = match blocks.last() {
+            Some(block) => block.end == *address && !leaders.contains(address),
+            None => false,
+        };
+        if !extends {
+            blocks.push(Block {
+                start: *address,
+                end: *address,
+                lines: Vec::new(),
+                exit: Exit::IntoData,
+                reachable: false,
+                self_modified: false,
+            });
+        }
+
+        let block = blocks.last_mut().unwrap();
+        block.end = address + instruction.size;
+        block.lines.push(Line::Instruction {
+            address: *address,
+            instruction: *instruction,
+            operands: operands(program, *address, instruction).to_vec(),
+        });
+    }
+
+    blocks
+}
+
+/// Works out how `block` exits, returning its outgoing edges.
+fn link(program: &[i64], code: &BTreeMap<usize, Instruction>, block: &mut Block) -> Vec<Edge> {
+    let (address, instruction) = match block.lines.last() {
+        Some(Line::Instruction {
+            address,
+            instruction,
+            ..
+        }) => (*address, *instruction),
+        _ => return Vec::new(),
+    };
+    let operands = operands(program, address, &instruction);
+    let (from, end) = (block.start, block.end);
+    let mut edges = Vec::new();
+    let mut edge = |to: usize, kind: EdgeKind| {
+        if code.contains_key(&to) {
+            edges.push(Edge { from, to, kind });
+        }
+    };
+
+    block.exit = if instruction.op == decode::HALT {
+        Exit::Halt
+    } else if !is_jump(&instruction) || is_never_taken(&instruction, operands) {
+        edge(end, EdgeKind::Fallthrough);
+        if code.contains_key(&end) {
+            Exit::Fallthrough
+        } else {
+            Exit::IntoData
+        }
+    } else if is_return(&instruction, operands) {
+        Exit::Return
+    } else if disassembler::is_unconditional_jump(&instruction, operands) {
+        match disassembler::get_jump_target(&instruction, operands) {
+            Some(target) if disassembler::is_call(program, code, address, instruction.size) => {
+                edge(target, EdgeKind::Call);
+                edge(end, EdgeKind::Return);
+                Exit::Call
+            }
+            Some(target) => {
+                edge(target, EdgeKind::Jump);
+                Exit::Jump
+            }
+            None => Exit::Indirect,
+        }
+    } else {
+        edge(end, EdgeKind::Fallthrough);
+        match disassembler::get_jump_target(&instruction, operands) {
+            Some(target) => {
+                edge(target, EdgeKind::Jump);
+                Exit::Branch
+            }
+            None => Exit::Indirect,
+        }
+    };
+
+    edges
+}
+
+fn find_writes(
+    program: &[i64],
+    code: &BTreeMap<usize, Instruction>,
+    blocks: &[Block],
+) -> Vec<CodeWrite> {
+    let runs_into: BTreeSet<usize> = blocks
+        .iter()
+        .filter(|block| block.exit == Exit::IntoData)
+        .map(|block| block.end)
+        .collect();
+    let mut writes = Vec::new();
+
+    for (writer, instruction) in code {
+        let param = match decode::get_write_parameter(instruction.op) {
+            Some(param) if instruction.addressing[param] == 0 => param,
+            _ => continue,
+        };
+        let address = program[writer + 1 + param];
+        if address < 0 {
+            continue;
+        }
+        let address = address as usize;
+
+        let instruction = match code.range(..=address).next_back() {
+            Some((start, target)) if start + target.size > address => *start,
+            _ if runs_into.contains(&address) => address,
+            _ => continue,
+        };
+        writes.push(CodeWrite {
+            writer: *writer,
+            address,
+            instruction,
+        });
+    }
+
+    // Operands of rewritten instructions are only their initial values.
+    let rewritten: BTreeSet<usize> = writes.iter().map(|write| write.instruction).collect();
+    writes.retain(|write| !rewritten.contains(&write.writer));
+    writes
+}
+
+impl Cfg {
+    pub fn analyze(program: &[i64]) -> Cfg {
+        let (code, _) = disassembler::find_code(program);
+        let mut blocks = find_blocks(program, &code);
+        let mut edges = Vec::new();
+        for block in blocks.iter_mut() {
+            edges.extend(link(program, &code, block));
+        }
+
+        let mut cfg = Cfg {
+            writes: find_writes(program, &code, &blocks),
+            blocks,
+            edges,
+            subroutines: Vec::new(),
+        };
+        cfg.mark_reachable();
+        for write in cfg.writes.clone() {
+            if let Some(block) = cfg.written_block(write.instruction) {
+                cfg.blocks[block].self_modified = true;
+            }
+        }
+        cfg.subroutines = cfg.find_subroutines();
+        cfg
+    }
+
+    /// Index of the block containing `address`.
+    fn block_index(&self, address: usize) -> Option<usize> {
+        let index = match self
+            .blocks
+            .binary_search_by(|block| block.start.cmp(&address))
+        {
+            Ok(index) => return Some(index),
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+
+        if self.blocks[index].end > address {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Index of the block containing `address`, or of the block that runs
+    /// into it.
+    fn written_block(&self, address: usize) -> Option<usize> {
+        self.block_index(address).or_else(|| {
+            self.blocks
+                .iter()
+                .position(|block| block.exit == Exit::IntoData && block.end == address)
+        })
+    }
+
+    /// The block containing `address`, if it is code.
+    pub fn block_at(&self, address: usize) -> Option<&Block> {
+        self.block_index(address).map(|index| &self.blocks[index])
+    }
+
+    fn successors(&self, start: usize) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |edge| edge.from == start)
+    }
+
+    /// Block starts that the block's instructions store as immediates, which
+    /// computed jumps and returns may later jump to.
+    fn taken_addresses(&self, block: &Block) -> Vec<usize> {
+        block
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Instruction {
+                    instruction,
+                    operands,
+                    ..
+                } => disassembler::get_immediate_result(instruction, operands),
+                _ => None,
+            })
+            .filter(|address| *address >= 0)
+            .map(|address| address as usize)
+            .filter(|address| self.block_index(*address).is_some())
+            .collect()
+    }
+
+    /// Marks blocks reachable from address 0 by edges or through addresses
+    /// that reachable code takes.
+    fn mark_reachable(&mut self) {
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        if let Some(index) = self.block_index(0) {
+            self.blocks[index].reachable = true;
+            queue.push_back(index);
+        }
+
+        while let Some(index) = queue.pop_front() {
+            let block = &self.blocks[index];
+            let mut targets: Vec<usize> =
+                self.successors(block.start).map(|edge| edge.to).collect();
+            targets.extend(self.taken_addresses(block));
+
+            for target in targets {
+                if let Some(index) = self.block_index(target) {
+                    if !self.blocks[index].reachable {
+                        self.blocks[index].reachable = true;
+                        queue.push_back(index);
+                    }
+                }
+            }
+        }
+    }
+
+    fn find_subroutines(&self) -> Vec<Subroutine> {
+        let mut callers: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for edge in self.edges.iter().filter(|edge| edge.kind == EdgeKind::Call) {
+            let block = self.block_at(edge.from).unwrap();
+            callers.entry(edge.to).or_default().push(block.last());
+        }
+
+        callers
+            .into_iter()
+            .map(|(entry, callers)| Subroutine {
+                entry,
+                frame: self.frame(entry),
+                callers,
+                returns: self.returns(entry),
+            })
+            .collect()
+    }
+
+    fn frame(&self, entry: usize) -> Option<i64> {
+        match self.block_at(entry)?.lines.first()? {
+            Line::Instruction {
+                address,
+                instruction,
+                operands,
+            } if *address == entry && instruction.op == 9 && instruction.addressing[0] == 1 => {
+                Some(operands[0])
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns reached from `entry` without entering other calls.
+    fn returns(&self, entry: usize) -> Vec<usize> {
+        let mut visited: BTreeSet<usize> = BTreeSet::new();
+        let mut stack = vec![entry];
+        let mut returns = Vec::new();
+
+        while let Some(start) = stack.pop() {
+            if !visited.insert(start) {
+                continue;
+            }
+            let block = match self.block_at(start) {
+                Some(block) => block,
+                None => continue,
+            };
+            if block.exit == Exit::Return {
+                returns.push(block.last());
+            }
+            stack.extend(
+                self.successors(block.start)
+                    .filter(|edge| edge.kind != EdgeKind::Call)
+                    .map(|edge| edge.to),
+            );
+        }
+
+        returns.sort_unstable();
+        returns
+    }
+
+    /// Graphviz source for the graph. Unreachable blocks are dashed and grey,
+    /// self-modified blocks red, and dashed red edges show writes into code.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let entries: BTreeMap<usize, &Subroutine> = self
+            .subroutines
+            .iter()
+            .map(|subroutine| (subroutine.entry, subroutine))
+            .collect();
+
+        writeln!(dot, "digraph intcode {{").unwrap();
+        writeln!(dot, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+        for block in self.blocks.iter() {
+            let mut label = String::new();
+            if let Some(subroutine) = entries.get(&block.start) {
+                match subroutine.frame {
+                    Some(frame) => write!(label, "sub_{} (frame {})\\l", block.start, frame),
+                    None => write!(label, "sub_{}\\l", block.start),
+                }
+                .unwrap();
+            }
+            for line in block.lines.iter() {
+                write!(label, "{}\\l", line.to_string().trim_start()).unwrap();
+            }
+
+            let mut attributes = vec![format!("label=\"{}\"", label)];
+            if entries.contains_key(&block.start) {
+                attributes.push("peripheries=2".to_string());
+            }
+            if block.self_modified {
+                attributes.push("color=red".to_string());
+            }
+            if !block.reachable {
+                attributes.push("style=dashed, fontcolor=gray".to_string());
+            }
+            writeln!(dot, "    b{} [{}];", block.start, attributes.join(", ")).unwrap();
+        }
+
+        for edge in self.edges.iter() {
+            let style = match edge.kind {
+                EdgeKind::Fallthrough => "",
+                EdgeKind::Jump => " [color=blue]",
+                EdgeKind::Call => " [style=bold, label=\"call\"]",
+                EdgeKind::Return => " [style=dotted, label=\"returns\"]",
+            };
+            writeln!(dot, "    b{} -> b{}{};", edge.from, edge.to, style).unwrap();
+        }
+        let writes: BTreeSet<(usize, usize, usize)> = self
+            .writes
+            .iter()
+            .filter_map(|write| {
+                let from = self.block_at(write.writer)?.start;
+                let to = self.blocks[self.written_block(write.address)?].start;
+                Some((from, to, write.address))
+            })
+            .collect();
+        for (from, to, address) in writes {
+            writeln!(
+                dot,
+                "    b{} -> b{} [style=dashed, color=red, constraint=false, label=\"writes {}\"];",
+                from, to, address
+            )
+            .unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+
+        dot
+    }
+}
@@ -0,0 +1,347 @@
+//! Ahead-of-time translation of Intcode programs to Rust source.
+//!
+//! `compile` emits a module holding the program and a `run` function that
+//! executes it over a `State`. Every instruction found by the disassembler
+//! becomes an arm of a `match ip` dispatcher with its operands inlined.
+//! Execution falls back to `Machine` at addresses that were not compiled and at
+//! instructions whose cells have been overwritten, until it reaches intact
+//! compiled code again.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::mem;
+
+use crate::decode;
+use crate::disassembler::{self, Line};
+use crate::error::MachineError;
+use crate::machine::{Machine, RunState};
+use crate::snapshot::Snapshot;
+
+/// Machine state shared by compiled code and the interpreter fallback.
+#[derive(Debug, Clone)]
+pub struct State {
+    pub memory: Vec<i64>,
+    pub ip: usize,
+    pub relative_base: i64,
+    pub input: VecDeque<i64>,
+    pub output: VecDeque<i64>,
+    /// The compiled program, to tell whether an instruction was overwritten.
+    original: Vec<i64>,
+    /// Start of the compiled instruction covering each cell of the program.
+    owner: Vec<Option<usize>>,
+    /// Compiled instructions, by start address, that no longer match memory.
+    stale: Vec<bool>,
+}
+
+impl State {
+    /// `instructions` lists the compiled instructions as `(address, size)`.
+    pub fn new(program: Vec<i64>, instructions: &[(usize, usize)]) -> State {
+        let mut owner = vec![None; program.len()];
+        for (address, size) in instructions {
+            for cell in owner.iter_mut().skip(*address).take(*size) {
+                *cell = Some(*address);
+            }
+        }
+
+        State {
+            stale: vec![false; program.len()],
+            original: program.clone(),
+            memory: program,
+            ip: 0,
+            relative_base: 0,
+            input: VecDeque::new(),
+            output: VecDeque::new(),
+            owner,
+        }
+    }
+
+    pub fn push_input(&mut self, input: i64) {
+        self.input.push_back(input);
+    }
+
+    pub fn pop_output(&mut self) -> Option<i64> {
+        self.output.pop_front()
+    }
+
+    /// Writes memory from outside the program, e.g. to patch it before running.
+    pub fn poke(&mut self, address: usize, value: i64) -> Result<(), MachineError> {
+        self.store(self.ip, address, value)
+    }
+
+    /// True if `ip` starts a compiled instruction that has not been overwritten.
+    pub fn is_compiled(&self, ip: usize) -> bool {
+        match self.owner.get(ip) {
+            Some(Some(start)) => *start == ip && !self.stale[ip],
+            _ => false,
+        }
+    }
+
+    fn mark_write(&mut self, address: usize, value: i64) {
+        if let Some(Some(start)) = self.owner.get(address) {
+            if value != self.original[address] {
+                self.stale[*start] = true;
+            }
+        }
+    }
+
+    pub fn address(&self, ip: usize, address: i64) -> Result<usize, MachineError> {
+        if address < 0 {
+            return Err(MachineError::NegativeAddress { ip, address });
+        }
+
+        Ok(address as usize)
+    }
+
+    pub fn relative(&self, ip: usize, offset: i64) -> Result<usize, MachineError> {
+        match self.relative_base.checked_add(offset) {
+            Some(address) => self.address(ip, address),
+            None => Err(MachineError::AddressOverflow { ip }),
+        }
+    }
+
+    pub fn target(&self, dest: i64) -> Result<usize, MachineError> {
+        if dest < 0 {
+            return Err(MachineError::IpOutOfRange { ip: dest });
+        }
+
+        Ok(dest as usize)
+    }
+
+    /// Checked like the interpreter's default `i64` words.
+    pub fn add(&self, ip: usize, lhs: i64, rhs: i64) -> Result<i64, MachineError> {
+        lhs.checked_add(rhs)
+            .ok_or(MachineError::ArithmeticOverflow { ip })
+    }
+
+    pub fn multiply(&self, ip: usize, lhs: i64, rhs: i64) -> Result<i64, MachineError> {
+        lhs.checked_mul(rhs)
+            .ok_or(MachineError::ArithmeticOverflow { ip })
+    }
+
+    pub fn load(&self, address: usize) -> i64 {
+        match self.memory.get(address) {
+            Some(value) => *value,
+            None => 0,
+        }
+    }
+
+    /// Writes memory, marking any compiled instruction it overwrites as stale.
+    pub fn store(&mut self, ip: usize, address: usize, value: i64) -> Result<(), MachineError> {
+        if address >= self.memory.len() {
+            if self
+                .memory
+                .try_reserve_exact(address + 1 - self.memory.len())
+                .is_err()
+            {
+                return Err(MachineError::MemoryLimitExceeded { ip, address });
+            }
+            self.memory.resize(address + 1, 0);
+        }
+
+        self.memory[address] = value;
+        self.mark_write(address, value);
+        Ok(())
+    }
+
+    pub fn adjust_relative_base(&mut self, ip: usize, adjustment: i64) -> Result<(), MachineError> {
+        self.relative_base = match self.relative_base.checked_add(adjustment) {
+            Some(relative_base) => relative_base,
+            None => return Err(MachineError::AddressOverflow { ip }),
+        };
+        Ok(())
+    }
+
+    /// Runs the interpreter until execution reaches a compiled instruction
+    /// again, returning the state if the machine stopped first.
+    pub fn interpret(&mut self) -> Option<RunState> {
+        let mut machine = Machine::from_snapshot(Snapshot {
+            memory: mem::take(&mut self.memory),
+            pages: Vec::new(),
+            ip: self.ip,
+            relative_base: self.relative_base,
+            input: self.input.drain(..).collect(),
+            output: self.output.drain(..).collect(),
+            cycles: 0,
+            halted: false,
+        });
+        let state = loop {
+            let state = machine.execute_instruction();
+            if let Some(write) = machine.last_write() {
+                self.mark_write(write.address, write.value);
+            }
+            if state.is_some() || self.is_compiled(machine.ip()) {
+                break state;
+            }
+        };
+        let snapshot = machine.snapshot();
+
+        self.memory = snapshot.memory;
+        self.ip = snapshot.ip;
+        self.relative_base = snapshot.relative_base;
+        self.input = snapshot.input.into_iter().collect();
+        self.output = snapshot.output.into_iter().collect();
+        state
+    }
+}
+
+fn format_list<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn value_expression(ip: usize, mode: i64, operand: i64) -> String {
+    match mode {
+        0 if operand >= 0 => format!("state.load({})", operand),
+        0 => format!("state.load(state.address({}, {})?)", ip, operand),
+        1 => operand.to_string(),
+        _ => format!("state.load(state.relative({}, {})?)", ip, operand),
+    }
+}
+
+fn address_expression(ip: usize, mode: i64, operand: i64) -> String {
+    match mode {
+        0 if operand >= 0 => operand.to_string(),
+        0 => format!("state.address({}, {})?", ip, operand),
+        _ => format!("state.relative({}, {})?", ip, operand),
+    }
+}
+
+/// Statements of the match arm for the instruction at `ip`.
+fn compile_instruction(
+    ip: usize,
+    instruction: &decode::Instruction,
+    operands: &[i64],
+) -> Vec<String> {
+    let value = |index: usize| value_expression(ip, instruction.addressing[index], operands[index]);
+    let address =
+        |index: usize| address_expression(ip, instruction.addressing[index], operands[index]);
+    let next = ip + instruction.size;
+
+    match instruction.op {
+        1 | 2 | 7 | 8 => {
+            let result = match instruction.op {
+                1 => format!("state.add({}, lhs, rhs)?", ip),
+                2 => format!("state.multiply({}, lhs, rhs)?", ip),
+                7 => "(lhs < rhs) as i64".to_string(),
+                _ => "(lhs == rhs) as i64".to_string(),
+            };
+            vec![
+                format!("let lhs = {};", value(0)),
+                format!("let rhs = {};", value(1)),
+                format!("let dest = {};", address(2)),
+                format!("state.store({}, dest, {})?;", ip, result),
+                format!("state.ip = {};", next),
+            ]
+        }
+        3 => vec![
+            format!("let dest = {};", address(0)),
+            "match state.input.front().cloned() {".to_string(),
+            format!("    Some(value) => state.store({}, dest, value)?,", ip),
+            "    None => return Ok(RunState::NeedsInput),".to_string(),
+            "}".to_string(),
+            "state.input.pop_front();".to_string(),
+            format!("state.ip = {};", next),
+        ],
+        4 => vec![
+            format!("state.output.push_back({});", value(0)),
+            format!("state.ip = {};", next),
+        ],
+        5 | 6 => vec![
+            format!("let condition = {};", value(0)),
+            format!("let dest = {};", value(1)),
+            format!(
+                "state.ip = if condition {} 0 {{ state.target(dest)? }} else {{ {} }};",
+                if instruction.op == 5 { "!=" } else { "==" },
+                next
+            ),
+        ],
+        9 => vec![
+            format!("let adjustment = {};", value(0)),
+            format!("state.adjust_relative_base({}, adjustment)?;", ip),
+            format!("state.ip = {};", next),
+        ],
+        _ => vec!["return Ok(RunState::Halted);".to_string()],
+    }
+}
+
+/// Translates `program` into the source of a Rust module called `name`, for
+/// crates depending on `intcode`. The module exposes `PROGRAM`, `state()` and
+/// `run(&mut State) -> RunState`, which behaves like `Machine::execute_program`.
+pub fn compile(program: &[i64], name: &str) -> String {
+    let mut instructions = Vec::new();
+    let mut arms = String::new();
+
+    for line in disassembler::disassemble(program) {
+        if let Line::Instruction {
+            address,
+            instruction,
+            operands,
+        } = line
+        {
+            instructions.push(format!("({}, {})", address, instruction.size));
+            writeln!(arms, "                {} => {{", address).unwrap();
+            for statement in compile_instruction(address, &instruction, &operands) {
+                writeln!(arms, "                    {}", statement).unwrap();
+            }
+            writeln!(arms, "                }}").unwrap();
+        }
+    }
+
+    let mut source = String::new();
+    writeln!(source, "#[allow(clippy::all)]").unwrap();
+    writeln!(source, "pub mod {} {{", name).unwrap();
+    writeln!(source, "    use intcode::compiler::State;").unwrap();
+    writeln!(source, "    use intcode::{{MachineError, RunState}};").unwrap();
+    writeln!(source).unwrap();
+    writeln!(
+        source,
+        "    pub const PROGRAM: [i64; {}] = [{}];",
+        program.len(),
+        format_list(program)
+    )
+    .unwrap();
+    writeln!(
+        source,
+        "    const INSTRUCTIONS: [(usize, usize); {}] = [{}];",
+        instructions.len(),
+        instructions.join(", ")
+    )
+    .unwrap();
+    source.push_str(
+        "
+    pub fn state() -> State {
+        State::new(PROGRAM.to_vec(), &INSTRUCTIONS)
+    }
+
+    pub fn run(state: &mut State) -> RunState {
+        match execute(state) {
+            Ok(run_state) => run_state,
+            Err(error) => RunState::Faulted(error),
+        }
+    }
+
+    fn execute(state: &mut State) -> Result<RunState, MachineError> {
+        loop {
+            if !state.is_compiled(state.ip) {
+                match state.interpret() {
+                    Some(run_state) => return Ok(run_state),
+                    None => continue,
+                }
+            }
+            match state.ip {
+",
+    );
+    source.push_str(&arms);
+    source.push_str(
+        "                _ => unreachable!(),
+            }
+        }
+    }
+}
+",
+    );
+    source
+}
@@ -0,0 +1,381 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::io::{BufRead, Write};
+
+use crate::disassembler;
+use crate::disassembler::Line;
+use crate::machine::{Machine, RunState};
+use crate::snapshot::Snapshot;
+
+const HELP: &str = "\
+step [n]            execute n instructions (default 1)
+continue            run until a breakpoint, watchpoint, input wait or halt
+back [n]            undo n instructions (default 1)
+rcontinue           run backwards to the previous breakpoint
+who <address>       show the last instruction that wrote to address
+break <ip>          set a breakpoint
+watch <address>     stop after writes to address
+delete <address>    remove a breakpoint or watchpoint
+info                list breakpoints and watchpoints
+regs                print ip, relative base and cycle count
+mem <start> [n]     dump n memory cells (default 8)
+dis [address] [n]   disassemble n instructions (default: ip, 5)
+queues              show pending input and output
+push <value>...     push input values
+pop                 pop and print all output
+save <file>         write a snapshot of the machine
+load <file>         restore the machine from a snapshot
+quit                exit the debugger";
+
+/// Instructions kept for stepping backwards.
+const HISTORY: usize = 100_000;
+
+/// Upper bound on the count given to `step`, `back`, `mem` and `dis`, so a
+/// mistyped count cannot flood the console or allocate without limit.
+const MAX_COUNT: usize = HISTORY;
+
+pub struct Debugger {
+    machine: Machine,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeSet<usize>,
+    state: Option<RunState>,
+}
+
+fn parse_args(args: &[&str]) -> Result<Vec<i64>, String> {
+    args.iter()
+        .map(|arg| arg.parse().map_err(|_| format!("invalid number `{}`", arg)))
+        .collect()
+}
+
+fn parse_address(arg: Option<&&str>) -> Result<usize, String> {
+    match arg {
+        Some(arg) => arg
+            .parse()
+            .map_err(|_| format!("invalid address `{}`", arg)),
+        None => Err("missing address".to_string()),
+    }
+}
+
+/// The count at `index` in `values`, or `default` when it is not given.
+fn parse_count(values: &[i64], index: usize, default: usize) -> Result<usize, String> {
+    match values.get(index) {
+        Some(count) if *count < 0 => Err(format!("invalid count `{}`", count)),
+        Some(count) => Ok((*count as u64).min(MAX_COUNT as u64) as usize),
+        None => Ok(default),
+    }
+}
+
+fn format_values(values: &[i64]) -> String {
+    if values.is_empty() {
+        return "(empty)".to_string();
+    }
+    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    values.join(", ")
+}
+
+impl Debugger {
+    pub fn new(mut machine: Machine) -> Debugger {
+        machine.set_history(Some(HISTORY));
+        Debugger {
+            machine,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            state: None,
+        }
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    fn current_line(&self, address: usize) -> Line {
+        disassembler::disassemble_instruction(|address| self.machine.read_memory(address), address)
+    }
+
+    fn line_size(line: &Line) -> usize {
+        match line {
+            Line::Instruction { instruction, .. } => instruction.size,
+            Line::Data { values, .. } => values.len(),
+        }
+    }
+
+    fn report_state<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        match &self.state {
+            Some(RunState::NeedsInput) => writeln!(output, "waiting for input"),
+            Some(RunState::Halted) => writeln!(output, "halted"),
+            Some(RunState::Faulted(error)) => writeln!(output, "fault: {}", error),
+            Some(RunState::Output(value)) => writeln!(output, "output {}", value),
+            Some(RunState::BudgetExhausted) => writeln!(output, "budget exhausted"),
+            None => Ok(()),
+        }
+    }
+
+    /// Executes one instruction, returning true if execution should stop.
+    fn step_once<W: Write>(&mut self, output: &mut W) -> io::Result<bool> {
+        let ip = self.machine.ip();
+        self.state = self.machine.execute_instruction();
+
+        if self.state.is_some() {
+            self.report_state(output)?;
+            return Ok(true);
+        }
+        if let Some(write) = self.machine.last_write() {
+            if self.watchpoints.contains(&write.address) {
+                writeln!(
+                    output,
+                    "watchpoint [{}]: {} -> {} at ip {}",
+                    write.address, write.old_value, write.value, ip
+                )?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn step<W: Write>(&mut self, count: usize, output: &mut W) -> io::Result<()> {
+        for _ in 0..count {
+            writeln!(output, "{}", self.current_line(self.machine.ip()))?;
+            if self.step_once(output)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn resume<W: Write>(&mut self, output: &mut W) -> io::Result<()> {
+        if self.step_once(output)? {
+            return Ok(());
+        }
+        while !self.breakpoints.contains(&self.machine.ip()) {
+            if self.step_once(output)? {
+                return Ok(());
+            }
+        }
+        writeln!(output, "breakpoint at ip {}", self.machine.ip())?;
+        writeln!(output, "{}", self.current_line(self.machine.ip()))
+    }
+
+    fn step_back<W: Write>(&mut self, count: usize, output: &mut W) -> io::Result<()> {
+        for _ in 0..count {
+            if !self.machine.step_back() {
+                writeln!(output, "start of history")?;
+                break;
+            }
+        }
+        self.state = None;
+        writeln!(output, "{}", self.current_line(self.machine.ip()))
+    }
+
+    fn resume_back<W: Write>(&mut self, output: &mut W) -> io::Result<()> {
+        self.state = None;
+        if self.machine.run_back_to_breakpoint(&self.breakpoints) {
+            writeln!(output, "breakpoint at ip {}", self.machine.ip())?;
+        } else {
+            writeln!(output, "start of history")?;
+        }
+        writeln!(output, "{}", self.current_line(self.machine.ip()))
+    }
+
+    fn who<W: Write>(&self, address: usize, output: &mut W) -> io::Result<()> {
+        let record = self
+            .machine
+            .history()
+            .and_then(|history| history.last_writer(address));
+        match record {
+            Some(record) => {
+                let write = record.write.as_ref().unwrap();
+                writeln!(
+                    output,
+                    "[{}]: {} -> {} at cycle {}",
+                    address, write.old_value, write.value, record.cycle
+                )?;
+                writeln!(output, "{}", self.current_line(record.ip))
+            }
+            None => writeln!(output, "[{}] not written in recorded history", address),
+        }
+    }
+
+    fn disassemble<W: Write>(&self, start: usize, count: usize, output: &mut W) -> io::Result<()> {
+        let mut address = start;
+        for _ in 0..count {
+            let line = self.current_line(address);
+            let marker = if address == self.machine.ip() {
+                "=>"
+            } else {
+                "  "
+            };
+
+            writeln!(output, "{}{}", marker, line)?;
+            address = match address.checked_add(Debugger::line_size(&line)) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        Ok(())
+    }
+
+    /// Runs a single command line, returning false when the session should end.
+    pub fn execute_command<W: Write>(&mut self, command: &str, output: &mut W) -> io::Result<bool> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let (name, args) = match words.split_first() {
+            Some((name, args)) => (*name, args),
+            None => return Ok(true),
+        };
+
+        let result: Result<(), String> = match name {
+            "s" | "step" => match parse_args(args).and_then(|values| parse_count(&values, 0, 1)) {
+                Ok(count) => {
+                    self.step(count, output)?;
+                    Ok(())
+                }
+                Err(message) => Err(message),
+            },
+            "c" | "continue" => {
+                self.resume(output)?;
+                Ok(())
+            }
+            "back" => match parse_args(args).and_then(|values| parse_count(&values, 0, 1)) {
+                Ok(count) => {
+                    self.step_back(count, output)?;
+                    Ok(())
+                }
+                Err(message) => Err(message),
+            },
+            "rc" | "rcontinue" => {
+                self.resume_back(output)?;
+                Ok(())
+            }
+            "who" => match parse_address(args.first()) {
+                Ok(address) => {
+                    self.who(address, output)?;
+                    Ok(())
+                }
+                Err(message) => Err(message),
+            },
+            "b" | "break" => parse_address(args.first()).map(|address| {
+                self.breakpoints.insert(address);
+            }),
+            "w" | "watch" => parse_address(args.first()).map(|address| {
+                self.watchpoints.insert(address);
+            }),
+            "d" | "delete" => parse_address(args.first()).map(|address| {
+                self.breakpoints.remove(&address);
+                self.watchpoints.remove(&address);
+            }),
+            "i" | "info" => {
+                let breakpoints: Vec<i64> = self.breakpoints.iter().map(|ip| *ip as i64).collect();
+                let watchpoints: Vec<i64> = self.watchpoints.iter().map(|ip| *ip as i64).collect();
+                writeln!(output, "breakpoints: {}", format_values(&breakpoints))?;
+                writeln!(output, "watchpoints: {}", format_values(&watchpoints))?;
+                Ok(())
+            }
+            "r" | "regs" => {
+                writeln!(
+                    output,
+                    "ip {} relative_base {} cycles {}",
+                    self.machine.ip(),
+                    self.machine.relative_base(),
+                    self.machine.cycles()
+                )?;
+                Ok(())
+            }
+            "m" | "mem" => match (
+                parse_address(args.first()),
+                parse_args(&args[args.len().min(1)..])
+                    .and_then(|values| parse_count(&values, 0, 8)),
+            ) {
+                (Ok(start), Ok(count)) => match start.checked_add(count) {
+                    Some(end) => {
+                        let values: Vec<i64> = (start..end)
+                            .map(|address| self.machine.read_memory(address))
+                            .collect();
+                        for (index, chunk) in values.chunks(8).enumerate() {
+                            writeln!(output, "{:>6}: {}", start + index * 8, format_values(chunk))?;
+                        }
+                        Ok(())
+                    }
+                    None => Err(format!(
+                        "{} cells from {} run past the last address",
+                        count, start
+                    )),
+                },
+                (Err(message), _) | (_, Err(message)) => Err(message),
+            },
+            "dis" => match (
+                args.first()
+                    .map_or(Ok(self.machine.ip()), |arg| parse_address(Some(arg))),
+                parse_args(&args[args.len().min(1)..])
+                    .and_then(|values| parse_count(&values, 0, 5)),
+            ) {
+                (Ok(start), Ok(count)) => {
+                    self.disassemble(start, count, output)?;
+                    Ok(())
+                }
+                (Err(message), _) | (_, Err(message)) => Err(message),
+            },
+            "queues" => {
+                let input: Vec<i64> = self.machine.input().iter().cloned().collect();
+                let pending: Vec<i64> = self.machine.output().iter().cloned().collect();
+                writeln!(output, "input: {}", format_values(&input))?;
+                writeln!(output, "output: {}", format_values(&pending))?;
+                Ok(())
+            }
+            "push" => parse_args(args).map(|values| {
+                for value in values {
+                    self.machine.push_input(value);
+                }
+            }),
+            "pop" => {
+                while let Some(value) = self.machine.pop_output() {
+                    writeln!(output, "{}", value)?;
+                }
+                Ok(())
+            }
+            "save" => match args.first() {
+                Some(filepath) => self
+                    .machine
+                    .snapshot()
+                    .save(filepath)
+                    .map_err(|error| format!("{}: {}", filepath, error)),
+                None => Err("missing filename".to_string()),
+            },
+            "load" => match args.first() {
+                Some(filepath) => match Snapshot::load(filepath) {
+                    Ok(snapshot) => {
+                        self.machine = Machine::from_snapshot(snapshot);
+                        self.machine.set_history(Some(HISTORY));
+                        self.state = None;
+                        Ok(())
+                    }
+                    Err(error) => Err(format!("{}: {}", filepath, error)),
+                },
+                None => Err("missing filename".to_string()),
+            },
+            "h" | "help" => {
+                writeln!(output, "{}", HELP)?;
+                Ok(())
+            }
+            "q" | "quit" => return Ok(false),
+            _ => Err(format!("unknown command `{}`, try `help`", name)),
+        };
+
+        if let Err(message) = result {
+            writeln!(output, "error: {}", message)?;
+        }
+        Ok(true)
+    }
+
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        write!(output, "(icdb) ")?;
+        output.flush()?;
+
+        for line in input.lines() {
+            if !self.execute_command(&line?, &mut output)? {
+                return Ok(());
+            }
+            write!(output, "(icdb) ")?;
+            output.flush()?;
+        }
+        writeln!(output)
+    }
+}
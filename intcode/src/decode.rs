@@ -0,0 +1,136 @@
+pub const HALT: i64 = 99;
+
+/// Operations take at most three parameters.
+pub const MAX_PARAMETERS: usize = 3;
+
+/// A decoded instruction. Modes past the operation's parameter count are zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instruction {
+    pub op: i64,
+    pub addressing: [i64; MAX_PARAMETERS],
+    pub size: usize,
+}
+
+impl Instruction {
+    /// Modes of the parameters the operation actually takes.
+    pub fn modes(&self) -> &[i64] {
+        &self.addressing[..self.size - 1]
+    }
+
+    /// Packs the instruction into a non-zero `u16`: the operation in the low
+    /// seven bits and two bits per mode above it.
+    pub fn pack(&self) -> u16 {
+        let modes = self
+            .addressing
+            .iter()
+            .rev()
+            .fold(0, |packed, mode| packed << 2 | *mode as u16);
+
+        self.op as u16 | modes << 7
+    }
+
+    /// Inverse of `pack`; only valid for values it produced.
+    pub fn unpack(packed: u16) -> Instruction {
+        let op = (packed & 0x7f) as i64;
+        let mut addressing = [0; MAX_PARAMETERS];
+        for (index, mode) in addressing.iter_mut().enumerate() {
+            *mode = (packed >> (7 + 2 * index) & 0b11) as i64;
+        }
+
+        Instruction {
+            op,
+            addressing,
+            size: get_operation_size(op).unwrap(),
+        }
+    }
+}
+
+pub fn get_operation_size(op: i64) -> Option<usize> {
+    match op {
+        1 | 2 | 7 | 8 => Some(4),
+        3 | 4 | 9 => Some(2),
+        5 | 6 => Some(3),
+        HALT => Some(1),
+        _ => None,
+    }
+}
+
+pub fn get_mnemonic(op: i64) -> Option<&'static str> {
+    match op {
+        1 => Some("add"),
+        2 => Some("mul"),
+        3 => Some("in"),
+        4 => Some("out"),
+        5 => Some("jnz"),
+        6 => Some("jz"),
+        7 => Some("lt"),
+        8 => Some("eq"),
+        9 => Some("arb"),
+        HALT => Some("hlt"),
+        _ => None,
+    }
+}
+
+pub fn get_operation(mnemonic: &str) -> Option<i64> {
+    match mnemonic {
+        "add" => Some(1),
+        "mul" => Some(2),
+        "in" => Some(3),
+        "out" => Some(4),
+        "jnz" => Some(5),
+        "jz" => Some(6),
+        "lt" => Some(7),
+        "eq" => Some(8),
+        "arb" => Some(9),
+        "hlt" => Some(HALT),
+        _ => None,
+    }
+}
+
+/// Index of the parameter the operation writes to, if any.
+pub fn get_write_parameter(op: i64) -> Option<usize> {
+    match op {
+        1 | 2 | 7 | 8 => Some(2),
+        3 => Some(0),
+        _ => None,
+    }
+}
+
+/// Splits the mode digits of an instruction; the error carries the first unknown mode.
+pub fn get_addressing(input: i64, op_size: usize) -> Result<[i64; MAX_PARAMETERS], i64> {
+    let mut temp = input;
+    let mut addressing = [0; MAX_PARAMETERS];
+
+    for mode_slot in addressing.iter_mut().take(op_size - 1) {
+        let mode = temp % 10;
+        if !(0..=2).contains(&mode) {
+            return Err(mode);
+        }
+
+        *mode_slot = mode;
+        temp /= 10;
+    }
+
+    Ok(addressing)
+}
+
+pub fn decode(value: i64) -> Option<Instruction> {
+    let op = value % 100;
+    let size = get_operation_size(op)?;
+    let addressing = get_addressing(value / 100, size).ok()?;
+
+    if value / 100 >= 10_i64.pow(size as u32 - 1) {
+        return None;
+    }
+    if let Some(param) = get_write_parameter(op) {
+        if addressing[param] == 1 {
+            return None;
+        }
+    }
+
+    Some(Instruction {
+        op,
+        addressing,
+        size,
+    })
+}
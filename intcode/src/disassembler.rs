@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::decode;
+use crate::decode::Instruction;
+
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line {
+    Instruction {
+        address: usize,
+        instruction: Instruction,
+        operands: Vec<i64>,
+    },
+    Data {
+        address: usize,
+        values: Vec<i64>,
+        note: Option<&'static str>,
+    },
+}
+
+pub fn format_operand(mode: i64, value: i64) -> String {
+    match mode {
+        0 => format!("[{}]", value),
+        1 => format!("#{}", value),
+        _ => {
+            if value < 0 {
+                format!("rb{}", value)
+            } else {
+                format!("rb+{}", value)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Line::Instruction {
+                address,
+                instruction,
+                operands,
+            } => {
+                let mnemonic = decode::get_mnemonic(instruction.op).unwrap();
+                let operands: Vec<String> = instruction
+                    .modes()
+                    .iter()
+                    .zip(operands.iter())
+                    .map(|(mode, value)| format_operand(*mode, *value))
+                    .collect();
+
+                let text = format!("{:<4} {}", mnemonic, operands.join(", "));
+
+                write!(f, "{:>6}: {}", address, text.trim_end())?;
+            }
+            Line::Data {
+                address,
+                values,
+                note,
+            } => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+
+                write!(f, "{:>6}: {:<4} {}", address, "db", values.join(", "))?;
+                if let Some(note) = note {
+                    write!(f, " ; {}", note)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn decode_at(program: &[i64], address: usize) -> Option<Instruction> {
+    let instruction = decode::decode(program[address])?;
+
+    if address + instruction.size > program.len() {
+        return None;
+    }
+    Some(instruction)
+}
+
+pub(crate) fn get_immediate_result(instruction: &Instruction, operands: &[i64]) -> Option<i64> {
+    if instruction.op != 1 && instruction.op != 2 {
+        return None;
+    }
+    if instruction.addressing[0] != 1 || instruction.addressing[1] != 1 {
+        return None;
+    }
+    match instruction.op {
+        1 => operands[0].checked_add(operands[1]),
+        2 => operands[0].checked_mul(operands[1]),
+        _ => None,
+    }
+}
+
+/// True if the jump at `address` is preceded, within its run of straight-line
+/// code, by an instruction storing the address just after the jump.
+pub(crate) fn is_call(
+    program: &[i64],
+    code: &BTreeMap<usize, Instruction>,
+    address: usize,
+    size: usize,
+) -> bool {
+    let return_address = (address + size) as i64;
+    let mut end = address;
+
+    while let Some((start, instruction)) = code.range(..end).next_back() {
+        if start + instruction.size != end || is_terminator(instruction) {
+            return false;
+        }
+        if get_immediate_result(instruction, &program[start + 1..end]) == Some(return_address) {
+            return true;
+        }
+        end = *start;
+    }
+    false
+}
+
+fn is_covered(code: &BTreeMap<usize, Instruction>, address: usize) -> bool {
+    match code.range(..=address).next_back() {
+        Some((start, instruction)) => start + instruction.size > address,
+        None => false,
+    }
+}
+
+/// Halts and jumps, the instructions that can end a run of straight-line code.
+fn is_terminator(instruction: &Instruction) -> bool {
+    instruction.op == decode::HALT || instruction.op == 5 || instruction.op == 6
+}
+
+pub(crate) fn is_unconditional_jump(instruction: &Instruction, operands: &[i64]) -> bool {
+    (instruction.op == 5 || instruction.op == 6)
+        && instruction.addressing[0] == 1
+        && (operands[0] != 0) == (instruction.op == 5)
+}
+
+pub(crate) fn get_jump_target(instruction: &Instruction, operands: &[i64]) -> Option<usize> {
+    if instruction.op != 5 && instruction.op != 6 {
+        return None;
+    }
+    if instruction.addressing[1] != 1 || operands[1] < 0 {
+        return None;
+    }
+    Some(operands[1] as usize)
+}
+
+/// Linear decode from an address nothing jumps to statically. Only accepted if it
+/// reaches a jump or halt without running into undecodable cells or known code.
+fn sweep(
+    program: &[i64],
+    code: &BTreeMap<usize, Instruction>,
+    mut address: usize,
+) -> Option<Vec<(usize, Instruction)>> {
+    let mut run: Vec<(usize, Instruction)> = Vec::new();
+
+    loop {
+        if address >= program.len() || is_covered(code, address) {
+            return None;
+        }
+        let instruction = decode_at(program, address)?;
+        let size = instruction.size;
+        let is_terminator = instruction.op == decode::HALT
+            || is_unconditional_jump(&instruction, &program[address + 1..address + size]);
+
+        run.push((address, instruction));
+        if is_terminator {
+            return Some(run);
+        }
+        address += size;
+    }
+}
+
+pub(crate) fn find_code(program: &[i64]) -> (BTreeMap<usize, Instruction>, BTreeSet<usize>) {
+    let mut code: BTreeMap<usize, Instruction> = BTreeMap::new();
+    let mut undecodable: BTreeSet<usize> = BTreeSet::new();
+    let mut to_visit: Vec<usize> = vec![0];
+    let mut speculative: Vec<usize> = Vec::new();
+
+    loop {
+        while let Some(address) = to_visit.pop() {
+            if address >= program.len() || code.contains_key(&address) {
+                continue;
+            }
+            let instruction = match decode_at(program, address) {
+                Some(instruction) => instruction,
+                None => {
+                    undecodable.insert(address);
+                    speculative.extend((2..5).map(|size| address + size));
+                    continue;
+                }
+            };
+            let size = instruction.size;
+            let operands = &program[address + 1..address + size];
+
+            if let Some(target) = get_jump_target(&instruction, operands) {
+                to_visit.push(target);
+            }
+            if instruction.op == decode::HALT {
+                code.insert(address, instruction);
+                continue;
+            }
+            if is_unconditional_jump(&instruction, operands) {
+                if is_call(program, &code, address, size) {
+                    to_visit.push(address + size);
+                } else {
+                    speculative.push(address + size);
+                }
+            } else {
+                to_visit.push(address + size);
+            }
+            code.insert(address, instruction);
+        }
+
+        match speculative.pop() {
+            Some(address) => {
+                if let Some(run) = sweep(program, &code, address) {
+                    to_visit.extend(run.iter().map(|(start, _)| *start));
+                }
+            }
+            None => break,
+        }
+    }
+
+    (code, undecodable)
+}
+
+fn find_write_targets(program: &[i64], code: &BTreeMap<usize, Instruction>) -> BTreeSet<usize> {
+    let mut targets: BTreeSet<usize> = BTreeSet::new();
+
+    for (start, instruction) in code {
+        if let Some(param) = decode::get_write_parameter(instruction.op) {
+            let target = program[start + 1 + param];
+            if instruction.addressing[param] == 0 && target >= 0 {
+                targets.insert(target as usize);
+            }
+        }
+    }
+
+    targets
+}
+
+fn push_data(lines: &mut Vec<Line>, address: usize, value: i64, note: Option<&'static str>) {
+    if let Some(Line::Data {
+        address: start,
+        values,
+        note: last_note,
+    }) = lines.last_mut()
+    {
+        if *last_note == note && *start + values.len() == address && values.len() < DATA_PER_LINE {
+            values.push(value);
+            return;
+        }
+    }
+    lines.push(Line::Data {
+        address,
+        values: vec![value],
+        note,
+    });
+}
+
+pub fn disassemble(program: &[i64]) -> Vec<Line> {
+    let (code, undecodable) = find_code(program);
+    let write_targets = find_write_targets(program, &code);
+    let modified: BTreeSet<usize> = code
+        .iter()
+        .filter(|(start, instruction)| {
+            write_targets
+                .range(**start..*start + instruction.size)
+                .next()
+                .is_some()
+        })
+        .map(|(start, _)| *start)
+        .collect();
+    let mut lines: Vec<Line> = Vec::new();
+    let mut address = 0;
+
+    while address < program.len() {
+        match code.get(&address) {
+            Some(instruction) if modified.contains(&address) => {
+                lines.push(Line::Data {
+                    address,
+                    values: program[address..address + instruction.size].to_vec(),
+                    note: Some("self-modifying"),
+                });
+                address += instruction.size;
+            }
+            Some(instruction) => {
+                lines.push(Line::Instruction {
+                    address,
+                    instruction: *instruction,
+                    operands: program[address + 1..address + instruction.size].to_vec(),
+                });
+                address += instruction.size;
+            }
+            None => {
+                let note = if write_targets.contains(&address) {
+                    Some("self-modifying")
+                } else if undecodable.contains(&address) {
+                    Some("undecodable")
+                } else {
+                    None
+                };
+                push_data(&mut lines, address, program[address], note);
+                address += 1;
+            }
+        }
+    }
+
+    lines
+}
+
+/// Decodes the single instruction at `address` regardless of reachability,
+/// reading memory through `read`.
+pub fn disassemble_instruction<F: Fn(usize) -> i64>(read: F, address: usize) -> Line {
+    let value = read(address);
+
+    match decode::decode(value) {
+        Some(instruction) => {
+            let operands = (1..instruction.size)
+                .map(|offset| address.checked_add(offset).map_or(0, &read))
+                .collect();
+
+            Line::Instruction {
+                address,
+                instruction,
+                operands,
+            }
+        }
+        None => Line::Data {
+            address,
+            values: vec![value],
+            note: Some("undecodable"),
+        },
+    }
+}
@@ -0,0 +1,49 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MachineError {
+    UnknownOperation { ip: usize, value: i64 },
+    UnknownAddressing { ip: usize, mode: i64 },
+    NegativeAddress { ip: usize, address: i64 },
+    WriteToImmediate { ip: usize },
+    IpOutOfRange { ip: i64 },
+    AddressOverflow { ip: usize },
+    MemoryLimitExceeded { ip: usize, address: usize },
+    ArithmeticOverflow { ip: usize },
+    WordOutOfRange { ip: usize },
+}
+
+impl fmt::Display for MachineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MachineError::UnknownOperation { ip, value } => {
+                write!(f, "unknown operation {} at ip {}", value, ip)
+            }
+            MachineError::UnknownAddressing { ip, mode } => {
+                write!(f, "unknown addressing mode {} at ip {}", mode, ip)
+            }
+            MachineError::NegativeAddress { ip, address } => {
+                write!(f, "negative address {} at ip {}", address, ip)
+            }
+            MachineError::WriteToImmediate { ip } => {
+                write!(f, "write to immediate mode parameter at ip {}", ip)
+            }
+            MachineError::IpOutOfRange { ip } => write!(f, "ip {} out of range", ip),
+            MachineError::AddressOverflow { ip } => {
+                write!(f, "relative address overflow at ip {}", ip)
+            }
+            MachineError::MemoryLimitExceeded { ip, address } => {
+                write!(f, "memory limit exceeded writing {} at ip {}", address, ip)
+            }
+            MachineError::ArithmeticOverflow { ip } => {
+                write!(f, "arithmetic overflow at ip {}", ip)
+            }
+            MachineError::WordOutOfRange { ip } => {
+                write!(f, "operand does not fit in an i64 at ip {}", ip)
+            }
+        }
+    }
+}
+
+impl Error for MachineError {}
@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+
+use crate::machine::MemoryWrite;
+
+/// What one executed instruction changed, enough to undo it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record<W = i64> {
+    /// Cycle count before the instruction ran.
+    pub cycle: u64,
+    pub ip: usize,
+    pub relative_base: i64,
+    pub write: Option<MemoryWrite<W>>,
+    /// The input value the instruction consumed.
+    pub input: Option<W>,
+    /// The value the instruction output.
+    pub output: Option<W>,
+}
+
+/// The most recent executed instructions, oldest first. Once `capacity`
+/// records are held each new one drops the oldest.
+#[derive(Debug, Clone)]
+pub struct History<W = i64> {
+    records: VecDeque<Record<W>>,
+    capacity: usize,
+}
+
+impl<W> History<W> {
+    pub fn new(capacity: usize) -> History<W> {
+        History {
+            records: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub(crate) fn push(&mut self, record: Record<W>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<Record<W>> {
+        self.records.pop_back()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn records(&self) -> impl DoubleEndedIterator<Item = &Record<W>> {
+        self.records.iter()
+    }
+
+    /// The most recent recorded instruction that wrote to `address`. `None`
+    /// means the cell has not been written since the oldest record.
+    pub fn last_writer(&self, address: usize) -> Option<&Record<W>> {
+        self.records.iter().rev().find(|record| {
+            record
+                .write
+                .as_ref()
+                .is_some_and(|write| write.address == address)
+        })
+    }
+}
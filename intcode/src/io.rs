@@ -0,0 +1,131 @@
+//! Pluggable input and output ports for `Machine::run_with`.
+//!
+//! Queues, `mpsc` channels, closures and the standard streams all work as
+//! ports, so a machine can run on its own thread wired straight to others.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+
+/// Source of values for input instructions.
+pub trait IntcodeInput {
+    /// Next input value, or `None` if there is none, which leaves the machine
+    /// waiting for input.
+    fn read(&mut self) -> Option<i64>;
+}
+
+/// Sink for values from output instructions.
+pub trait IntcodeOutput {
+    fn write(&mut self, value: i64);
+}
+
+impl IntcodeInput for VecDeque<i64> {
+    fn read(&mut self) -> Option<i64> {
+        self.pop_front()
+    }
+}
+
+impl IntcodeOutput for VecDeque<i64> {
+    fn write(&mut self, value: i64) {
+        self.push_back(value);
+    }
+}
+
+impl IntcodeOutput for Vec<i64> {
+    fn write(&mut self, value: i64) {
+        self.push(value);
+    }
+}
+
+/// Blocks until a value arrives; a disconnected channel has no more input.
+impl IntcodeInput for Receiver<i64> {
+    fn read(&mut self) -> Option<i64> {
+        self.recv().ok()
+    }
+}
+
+/// Values sent after the receiver hangs up are dropped.
+impl IntcodeOutput for Sender<i64> {
+    fn write(&mut self, value: i64) {
+        let _ = self.send(value);
+    }
+}
+
+impl IntcodeOutput for SyncSender<i64> {
+    fn write(&mut self, value: i64) {
+        let _ = self.send(value);
+    }
+}
+
+impl<F: FnMut() -> Option<i64>> IntcodeInput for F {
+    fn read(&mut self) -> Option<i64> {
+        self()
+    }
+}
+
+impl<F: FnMut(i64)> IntcodeOutput for F {
+    fn write(&mut self, value: i64) {
+        self(value)
+    }
+}
+
+/// Reads whitespace or comma separated values from a reader, one line at a
+/// time. End of input or a value that does not parse ends the input.
+pub struct LineInput<R: BufRead> {
+    reader: R,
+    pending: VecDeque<i64>,
+}
+
+impl<R: BufRead> LineInput<R> {
+    pub fn new(reader: R) -> LineInput<R> {
+        LineInput {
+            reader,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: BufRead> IntcodeInput for LineInput<R> {
+    fn read(&mut self) -> Option<i64> {
+        while self.pending.is_empty() {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            for token in line.split(|c: char| c == ',' || c.is_whitespace()) {
+                if !token.is_empty() {
+                    self.pending.push_back(token.parse().ok()?);
+                }
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// Writes each value on its own line, flushing so interactive programs show
+/// output before they block on input.
+pub struct LineOutput<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> LineOutput<W> {
+    pub fn new(writer: W) -> LineOutput<W> {
+        LineOutput { writer }
+    }
+}
+
+impl<W: Write> IntcodeOutput for LineOutput<W> {
+    fn write(&mut self, value: i64) {
+        let _ = writeln!(self.writer, "{}", value).and_then(|_| self.writer.flush());
+    }
+}
+
+/// Blocking input from standard input.
+pub fn stdin() -> LineInput<io::StdinLock<'static>> {
+    LineInput::new(io::stdin().lock())
+}
+
+/// Output to standard output.
+pub fn stdout() -> LineOutput<io::Stdout> {
+    LineOutput::new(io::stdout())
+}
@@ -0,0 +1,41 @@
+use std::fs;
+
+pub mod amplifier;
+pub mod ascii;
+pub mod assembler;
+pub mod cfg;
+pub mod compiler;
+pub mod debugger;
+pub mod decode;
+pub mod disassembler;
+mod error;
+pub mod history;
+pub mod io;
+mod machine;
+pub mod memory;
+pub mod network;
+pub mod patch;
+pub mod profile;
+mod snapshot;
+pub mod symbolic;
+pub mod trace;
+pub mod word;
+
+pub use error::MachineError;
+pub use io::{IntcodeInput, IntcodeOutput};
+pub use machine::{Machine, MemoryWrite, RunState};
+pub use snapshot::Snapshot;
+pub use word::Word;
+
+pub fn parse_program(contents: &str) -> Vec<i64> {
+    contents
+        .trim()
+        .split(',')
+        .map(|input| input.trim().parse().unwrap())
+        .collect()
+}
+
+pub fn tokenize(filepath: &str) -> Vec<i64> {
+    let contents = fs::read_to_string(filepath).expect("Error reading file");
+    parse_program(&contents)
+}
@@ -0,0 +1,836 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::thread::{self, JoinHandle};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ascii::{self, Ascii};
+use crate::decode::{self, Instruction};
+use crate::error::MachineError;
+use crate::history::{History, Record};
+use crate::io::{IntcodeInput, IntcodeOutput};
+use crate::memory::{DenseMemory, Memory, PagedMemory};
+use crate::profile::Profile;
+use crate::snapshot::Snapshot;
+use crate::trace::{Operand, Tracer};
+use crate::word::{self, Word};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunState<W = i64> {
+    NeedsInput,
+    Output(W),
+    Halted,
+    Faulted(MachineError),
+    /// The step budget ran out before the next instruction. Raising the budget
+    /// with `set_budget` and running again resumes from the same instruction.
+    BudgetExhausted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MemoryWrite<W = i64> {
+    pub address: usize,
+    pub old_value: W,
+    pub value: W,
+}
+
+/// An Intcode machine computing with words of type `W`; see `word` for the
+/// choices. I/O ports, snapshots, tracing and the ASCII helpers need `i64`.
+pub struct Machine<W = i64> {
+    memory: Box<dyn Memory<W>>,
+    /// Packed instructions decoded so far, indexed by address, with zero for
+    /// cells not decoded yet. Covers the memory
+    /// allocated when the machine was created; code beyond it is decoded on
+    /// every visit.
+    decoded: Vec<u16>,
+    ip: usize,
+    relative_base: i64,
+    input: VecDeque<W>,
+    output: VecDeque<W>,
+    last_write: Option<MemoryWrite<W>>,
+    /// Set when HALT executes, so running a halted machine again does not
+    /// trace or profile the HALT a second time.
+    halted: bool,
+    cycles: u64,
+    budget: Option<u64>,
+    history: Option<History<W>>,
+    profile: Option<Profile>,
+    tracer: Option<Tracer>,
+}
+
+/// Clones do not inherit the tracer or profile.
+impl<W: Word> Clone for Machine<W> {
+    fn clone(&self) -> Machine<W> {
+        Machine {
+            memory: self.memory.clone_box(),
+            decoded: self.decoded.clone(),
+            ip: self.ip,
+            relative_base: self.relative_base,
+            input: self.input.clone(),
+            output: self.output.clone(),
+            last_write: self.last_write.clone(),
+            halted: self.halted,
+            cycles: self.cycles,
+            budget: self.budget,
+            history: self.history.clone(),
+            profile: None,
+            tracer: None,
+        }
+    }
+}
+
+impl<W: Word> Machine<W> {
+    /// Loads a parsed program as words of type `W`.
+    pub fn from_program(program: &[i64]) -> Machine<W> {
+        Machine::with_memory(Box::new(DenseMemory::new(word::convert(program))))
+    }
+
+    pub fn with_memory(memory: Box<dyn Memory<W>>) -> Machine<W> {
+        Machine {
+            decoded: vec![0; memory.allocated()],
+            memory,
+            ip: 0,
+            relative_base: 0,
+            input: VecDeque::<W>::new(),
+            output: VecDeque::<W>::new(),
+            last_write: None,
+            halted: false,
+            cycles: 0,
+            budget: None,
+            history: None,
+            profile: None,
+            tracer: None,
+        }
+    }
+
+    fn to_i64(&self, value: &W) -> Result<i64, MachineError> {
+        match value.to_i64() {
+            Some(value) => Ok(value),
+            None => Err(MachineError::WordOutOfRange { ip: self.ip }),
+        }
+    }
+
+    /// Tracers are only installed on `Machine<i64>`, where this is exact.
+    fn trace_value(value: &W) -> i64 {
+        value.to_i64().unwrap_or_default()
+    }
+
+    fn to_address(&self, address: i64) -> Result<usize, MachineError> {
+        if address < 0 {
+            return Err(MachineError::NegativeAddress {
+                ip: self.ip,
+                address,
+            });
+        }
+
+        Ok(address as usize)
+    }
+
+    fn get_address(&self, addressing: i64, pos: usize) -> Result<usize, MachineError> {
+        match addressing {
+            0 => self.to_address(self.to_i64(&self.read_memory(pos))?),
+            1 => Ok(pos),
+            2 => {
+                let offset = self.to_i64(&self.read_memory(pos))?;
+                match self.relative_base.checked_add(offset) {
+                    Some(address) => self.to_address(address),
+                    None => Err(MachineError::AddressOverflow { ip: self.ip }),
+                }
+            }
+            _ => Err(MachineError::UnknownAddressing {
+                ip: self.ip,
+                mode: addressing,
+            }),
+        }
+    }
+
+    fn get_write_address(&self, addressing: i64, pos: usize) -> Result<usize, MachineError> {
+        if addressing == 1 {
+            return Err(MachineError::WriteToImmediate { ip: self.ip });
+        }
+
+        self.get_address(addressing, pos)
+    }
+
+    fn get_value(&self, addressing: i64, pos: usize) -> Result<W, MachineError> {
+        let address = self.get_address(addressing, pos)?;
+
+        Ok(self.read_memory(address))
+    }
+
+    fn write_to_memory(&mut self, address: usize, input: W) -> Result<(), MachineError> {
+        let old_value = self.memory.read(address);
+
+        if !self.memory.write(address, input.clone()) {
+            return Err(MachineError::MemoryLimitExceeded {
+                ip: self.ip,
+                address,
+            });
+        }
+        // Decoding only looks at the opcode cell, so that is the only entry a
+        // self-modifying write can make stale.
+        if let Some(entry) = self.decoded.get_mut(address) {
+            *entry = 0;
+        }
+        self.last_write = Some(MemoryWrite {
+            address,
+            old_value,
+            value: input,
+        });
+        Ok(())
+    }
+
+    fn jump(&mut self, dest: W) -> Result<Option<RunState<W>>, MachineError> {
+        let dest = self.to_i64(&dest)?;
+        if dest < 0 {
+            return Err(MachineError::IpOutOfRange { ip: dest });
+        }
+
+        self.ip = dest as usize;
+        Ok(None)
+    }
+
+    fn get_operation_size(&self, opcode: i64) -> Result<usize, MachineError> {
+        let op = opcode % 100;
+        match decode::get_operation_size(op) {
+            Some(size) if op != decode::HALT => Ok(size),
+            _ => Err(MachineError::UnknownOperation {
+                ip: self.ip,
+                value: opcode,
+            }),
+        }
+    }
+
+    fn add(&mut self, instruction: Instruction) -> Result<Option<RunState<W>>, MachineError> {
+        let lhs = self.get_value(instruction.addressing[0], self.ip + 1)?;
+        let rhs = self.get_value(instruction.addressing[1], self.ip + 2)?;
+        let dest = self.get_write_address(instruction.addressing[2], self.ip + 3)?;
+
+        let sum = match lhs.add(&rhs) {
+            Some(sum) => sum,
+            None => return Err(MachineError::ArithmeticOverflow { ip: self.ip }),
+        };
+        self.write_to_memory(dest, sum)?;
+        self.ip += instruction.size;
+        Ok(None)
+    }
+
+    fn multiply(&mut self, instruction: Instruction) -> Result<Option<RunState<W>>, MachineError> {
+        let lhs = self.get_value(instruction.addressing[0], self.ip + 1)?;
+        let rhs = self.get_value(instruction.addressing[1], self.ip + 2)?;
+        let dest = self.get_write_address(instruction.addressing[2], self.ip + 3)?;
+
+        let product = match lhs.multiply(&rhs) {
+            Some(product) => product,
+            None => return Err(MachineError::ArithmeticOverflow { ip: self.ip }),
+        };
+        self.write_to_memory(dest, product)?;
+        self.ip += instruction.size;
+        Ok(None)
+    }
+
+    fn write(&mut self, instruction: Instruction) -> Result<Option<RunState<W>>, MachineError> {
+        let dest = self.get_write_address(instruction.addressing[0], self.ip + 1)?;
+
+        // The input is only consumed once the write succeeds, so a faulted
+        // instruction can be retried.
+        match self.input.front().cloned() {
+            Some(item) => {
+                self.write_to_memory(dest, item)?;
+                self.input.pop_front();
+                self.ip += instruction.size;
+                Ok(None)
+            }
+            None => Ok(Some(RunState::NeedsInput)),
+        }
+    }
+
+    fn read(&mut self, instruction: Instruction) -> Result<Option<RunState<W>>, MachineError> {
+        let value = self.get_value(instruction.addressing[0], self.ip + 1)?;
+
+        self.ip += instruction.size;
+        Ok(Some(RunState::Output(value)))
+    }
+
+    fn jump_if_false(
+        &mut self,
+        instruction: Instruction,
+    ) -> Result<Option<RunState<W>>, MachineError> {
+        let condition = self.get_value(instruction.addressing[0], self.ip + 1)?;
+        let dest = self.get_value(instruction.addressing[1], self.ip + 2)?;
+
+        if condition != W::default() {
+            self.jump(dest)
+        } else {
+            self.ip += instruction.size;
+            Ok(None)
+        }
+    }
+
+    fn jump_if_true(
+        &mut self,
+        instruction: Instruction,
+    ) -> Result<Option<RunState<W>>, MachineError> {
+        let condition = self.get_value(instruction.addressing[0], self.ip + 1)?;
+        let dest = self.get_value(instruction.addressing[1], self.ip + 2)?;
+
+        if condition == W::default() {
+            self.jump(dest)
+        } else {
+            self.ip += instruction.size;
+            Ok(None)
+        }
+    }
+
+    fn less_than(&mut self, instruction: Instruction) -> Result<Option<RunState<W>>, MachineError> {
+        let lhs = self.get_value(instruction.addressing[0], self.ip + 1)?;
+        let rhs = self.get_value(instruction.addressing[1], self.ip + 2)?;
+        let dest = self.get_write_address(instruction.addressing[2], self.ip + 3)?;
+
+        self.write_to_memory(dest, W::from_i64((lhs < rhs) as i64))?;
+        self.ip += instruction.size;
+        Ok(None)
+    }
+
+    fn equals(&mut self, instruction: Instruction) -> Result<Option<RunState<W>>, MachineError> {
+        let lhs = self.get_value(instruction.addressing[0], self.ip + 1)?;
+        let rhs = self.get_value(instruction.addressing[1], self.ip + 2)?;
+        let dest = self.get_write_address(instruction.addressing[2], self.ip + 3)?;
+
+        self.write_to_memory(dest, W::from_i64((lhs == rhs) as i64))?;
+        self.ip += instruction.size;
+        Ok(None)
+    }
+
+    fn adjust_relative_base(
+        &mut self,
+        instruction: Instruction,
+    ) -> Result<Option<RunState<W>>, MachineError> {
+        let adjustment = self.get_value(instruction.addressing[0], self.ip + 1)?;
+        let adjustment = self.to_i64(&adjustment)?;
+        self.relative_base = match self.relative_base.checked_add(adjustment) {
+            Some(relative_base) => relative_base,
+            None => return Err(MachineError::AddressOverflow { ip: self.ip }),
+        };
+
+        self.ip += instruction.size;
+        Ok(None)
+    }
+
+    fn execute_operation(
+        &mut self,
+        instruction: Instruction,
+    ) -> Result<Option<RunState<W>>, MachineError> {
+        match instruction.op {
+            1 => self.add(instruction),
+            2 => self.multiply(instruction),
+            3 => self.write(instruction),
+            4 => self.read(instruction),
+            5 => self.jump_if_false(instruction),
+            6 => self.jump_if_true(instruction),
+            7 => self.less_than(instruction),
+            8 => self.equals(instruction),
+            9 => self.adjust_relative_base(instruction),
+            _ => Err(MachineError::UnknownOperation {
+                ip: self.ip,
+                value: Self::trace_value(&self.read_memory(self.ip)),
+            }),
+        }
+    }
+
+    fn get_addressing(
+        &self,
+        input: i64,
+        op_size: usize,
+    ) -> Result<[i64; decode::MAX_PARAMETERS], MachineError> {
+        decode::get_addressing(input, op_size)
+            .map_err(|mode| MachineError::UnknownAddressing { ip: self.ip, mode })
+    }
+
+    fn resolve_operands(&self, addressing: &[i64]) -> Vec<Operand> {
+        addressing
+            .iter()
+            .enumerate()
+            .map_while(|(index, mode)| self.get_address(*mode, self.ip + 1 + index).ok())
+            .map(|address| Operand {
+                address,
+                value: Self::trace_value(&self.read_memory(address)),
+            })
+            .collect()
+    }
+
+    fn step(&mut self) -> Result<Option<RunState<W>>, MachineError> {
+        if self.halted {
+            return Ok(Some(RunState::Halted));
+        }
+        if self.budget == Some(0) {
+            return Ok(Some(RunState::BudgetExhausted));
+        }
+        if self.history.is_none() {
+            let state = self.step_instruction()?;
+            if state != Some(RunState::NeedsInput) && state != Some(RunState::Halted) {
+                self.count_cycle();
+            }
+            return Ok(state);
+        }
+
+        let (ip, relative_base) = (self.ip, self.relative_base);
+        let (input, pending) = (self.input.front().cloned(), self.input.len());
+        let state = self.step_instruction()?;
+        if state != Some(RunState::NeedsInput) && state != Some(RunState::Halted) {
+            let record = Record {
+                cycle: self.cycles,
+                ip,
+                relative_base,
+                write: self.last_write.clone(),
+                input: input.filter(|_| self.input.len() < pending),
+                output: match &state {
+                    Some(RunState::Output(value)) => Some(value.clone()),
+                    _ => None,
+                },
+            };
+            if let Some(history) = self.history.as_mut() {
+                history.push(record);
+            }
+            self.count_cycle();
+        }
+        Ok(state)
+    }
+
+    fn count_cycle(&mut self) {
+        self.cycles += 1;
+        if let Some(budget) = self.budget.as_mut() {
+            *budget -= 1;
+        }
+    }
+
+    fn decode_at(&mut self, ip: usize) -> Result<Instruction, MachineError> {
+        if let Some(packed) = self.decoded.get(ip).filter(|packed| **packed != 0) {
+            return Ok(Instruction::unpack(*packed));
+        }
+        if ip >= self.memory.extent() {
+            return Err(MachineError::IpOutOfRange { ip: ip as i64 });
+        }
+
+        let opcode = self.to_i64(&self.memory.read(ip))?;
+        let instruction = if opcode == decode::HALT {
+            Instruction {
+                op: decode::HALT,
+                addressing: [0; decode::MAX_PARAMETERS],
+                size: 1,
+            }
+        } else {
+            let op = opcode % 100;
+            let size = self.get_operation_size(opcode)?;
+            let addressing = self.get_addressing(opcode / 100, size)?;
+            Instruction {
+                op,
+                addressing,
+                size,
+            }
+        };
+
+        if let Some(entry) = self.decoded.get_mut(ip) {
+            *entry = instruction.pack();
+        }
+        Ok(instruction)
+    }
+
+    fn step_instruction(&mut self) -> Result<Option<RunState<W>>, MachineError> {
+        self.last_write = None;
+        let ip = self.ip;
+        let instruction = match self.decode_at(ip) {
+            Ok(instruction) => instruction,
+            Err(error) => {
+                if ip < self.memory.extent() {
+                    let opcode = Self::trace_value(&self.read_memory(ip));
+                    self.trace_fault(ip, opcode, Vec::new(), &error);
+                }
+                return Err(error);
+            }
+        };
+        if instruction.op == decode::HALT {
+            self.halted = true;
+            let relative_base = self.relative_base;
+            if let Some(profile) = self.profile.as_mut() {
+                profile.record(
+                    ip,
+                    &instruction,
+                    &[],
+                    None,
+                    ip,
+                    (relative_base, relative_base),
+                );
+            }
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.record(ip, decode::HALT, Vec::new(), None, relative_base, None);
+            }
+            return Ok(Some(RunState::Halted));
+        }
+
+        if self.tracer.is_none() && self.profile.is_none() {
+            return self.execute_operation(instruction);
+        }
+
+        let opcode = Self::trace_value(&self.read_memory(ip));
+        let operands = self.resolve_operands(instruction.modes());
+        let relative_base = self.relative_base;
+        let state = match self.execute_operation(instruction) {
+            Ok(state) => state,
+            Err(error) => {
+                self.trace_fault(ip, opcode, operands, &error);
+                return Err(error);
+            }
+        };
+        if state != Some(RunState::NeedsInput) {
+            let written = self.last_write.as_ref().map(|write| write.address);
+            let (next_ip, next_base) = (self.ip, self.relative_base);
+            if let Some(profile) = self.profile.as_mut() {
+                profile.record(
+                    ip,
+                    &instruction,
+                    &operands,
+                    written,
+                    next_ip,
+                    (relative_base, next_base),
+                );
+            }
+            let write = self.last_write.as_ref().map(|write| MemoryWrite {
+                address: write.address,
+                old_value: Self::trace_value(&write.old_value),
+                value: Self::trace_value(&write.value),
+            });
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.record(ip, opcode, operands, write, next_base, None);
+            }
+        }
+        Ok(state)
+    }
+
+    /// Traces an instruction that faulted, which has written nothing.
+    fn trace_fault(
+        &mut self,
+        ip: usize,
+        opcode: i64,
+        operands: Vec<Operand>,
+        error: &MachineError,
+    ) {
+        let relative_base = self.relative_base;
+        if let Some(tracer) = self.tracer.as_mut() {
+            let fault = Some(error.to_string());
+            tracer.record(ip, opcode, operands, None, relative_base, fault);
+        }
+    }
+
+    pub fn execute_program(&mut self) -> RunState<W> {
+        loop {
+            match self.step() {
+                Ok(None) => {}
+                Ok(Some(RunState::Output(value))) => self.output.push_back(value),
+                Ok(Some(state)) => return state,
+                Err(error) => return RunState::Faulted(error),
+            }
+        }
+    }
+
+    /// Executes a single instruction, queueing any output. Returns `None` while
+    /// the machine can keep running.
+    pub fn execute_instruction(&mut self) -> Option<RunState<W>> {
+        match self.step() {
+            Ok(Some(RunState::Output(value))) => {
+                self.output.push_back(value);
+                None
+            }
+            Ok(state) => state,
+            Err(error) => Some(RunState::Faulted(error)),
+        }
+    }
+
+    /// Like `execute_program`, but stops at the next output instead of queueing it.
+    pub fn run_until_output(&mut self) -> RunState<W> {
+        loop {
+            match self.step() {
+                Ok(None) => {}
+                Ok(Some(state)) => return state,
+                Err(error) => return RunState::Faulted(error),
+            }
+        }
+    }
+
+    pub fn push_input(&mut self, input: W) {
+        self.input.push_back(input);
+    }
+
+    pub fn pop_output(&mut self) -> Option<W> {
+        self.output.pop_front()
+    }
+
+    pub fn read_memory(&self, address: usize) -> W {
+        self.memory.read(address)
+    }
+
+    /// The decode cache is on by default; turning it off decodes every
+    /// instruction as it is executed.
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.decoded = if enabled {
+            vec![0; self.memory.allocated()]
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// Limits the memory cells backed by storage. Dense memory starts out
+    /// limited to `memory::DENSE_LIMIT` cells; `None` removes the limit.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory.set_limit(limit);
+    }
+
+    /// Memory cells backed by storage, which is what the limit applies to.
+    pub fn allocated(&self) -> usize {
+        self.memory.allocated()
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    pub fn input(&self) -> &VecDeque<W> {
+        &self.input
+    }
+
+    pub fn output(&self) -> &VecDeque<W> {
+        &self.output
+    }
+
+    pub fn last_write(&self) -> Option<MemoryWrite<W>> {
+        self.last_write.clone()
+    }
+
+    /// Number of instructions executed so far. Halting and blocking on input
+    /// do not count.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Limits how many more instructions may execute before the machine stops
+    /// with `RunState::BudgetExhausted`. `None` removes the limit.
+    pub fn set_budget(&mut self, budget: Option<u64>) {
+        self.budget = budget;
+    }
+
+    /// Instructions left before the budget is exhausted.
+    pub fn budget(&self) -> Option<u64> {
+        self.budget
+    }
+
+    /// Records the last `capacity` executed instructions so they can be undone
+    /// with `step_back`. `None` turns recording off; either way the current
+    /// history is dropped.
+    pub fn set_history(&mut self, capacity: Option<usize>) {
+        self.history = capacity.map(History::new);
+    }
+
+    pub fn history(&self) -> Option<&History<W>> {
+        self.history.as_ref()
+    }
+
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = Some(profile);
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take()
+    }
+
+    /// Undoes the most recent recorded instruction, restoring memory, ip,
+    /// relative base, consumed input and the cycle count. An output is taken
+    /// back off the output queue if it is still the newest value there.
+    /// Returns false when there is nothing left to undo.
+    pub fn step_back(&mut self) -> bool {
+        let record = match self.history.as_mut().and_then(|history| history.pop()) {
+            Some(record) => record,
+            None => return false,
+        };
+
+        if let Some(write) = record.write {
+            // The cell was allocated by the write being undone.
+            self.memory.write(write.address, write.old_value);
+            if let Some(entry) = self.decoded.get_mut(write.address) {
+                *entry = 0;
+            }
+        }
+        if let Some(input) = record.input {
+            self.input.push_front(input);
+        }
+        if record.output.is_some() && self.output.back() == record.output.as_ref() {
+            self.output.pop_back();
+        }
+        self.ip = record.ip;
+        self.relative_base = record.relative_base;
+        self.cycles = record.cycle;
+        self.halted = false;
+        if let Some(budget) = self.budget.as_mut() {
+            *budget += 1;
+        }
+        self.last_write = None;
+        true
+    }
+
+    /// Steps back at least once, then until the ip is one of `breakpoints`.
+    /// Returns false if the history ran out first.
+    pub fn run_back_to_breakpoint(&mut self, breakpoints: &BTreeSet<usize>) -> bool {
+        if !self.step_back() {
+            return false;
+        }
+        while !breakpoints.contains(&self.ip) {
+            if !self.step_back() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Machine {
+    pub fn new(program: Vec<i64>) -> Machine {
+        Machine::with_memory(Box::new(DenseMemory::new(program)))
+    }
+
+    /// Runs with external ports. Queued input is used before `input` is read,
+    /// and the machine stops with `NeedsInput` once `input` has nothing more.
+    pub fn run_with<I: IntcodeInput, O: IntcodeOutput>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+    ) -> RunState {
+        loop {
+            match self.step() {
+                Ok(None) => {}
+                Ok(Some(RunState::Output(value))) => output.write(value),
+                Ok(Some(RunState::NeedsInput)) => match input.read() {
+                    Some(value) => self.push_input(value),
+                    None => return RunState::NeedsInput,
+                },
+                Ok(Some(state)) => return state,
+                Err(error) => return RunState::Faulted(error),
+            }
+        }
+    }
+
+    /// Runs the machine on its own thread with `run_with`, handing it back
+    /// with its final state when it stops.
+    pub fn spawn<I, O>(mut self, mut input: I, mut output: O) -> JoinHandle<(Machine, RunState)>
+    where
+        I: IntcodeInput + Send + 'static,
+        O: IntcodeOutput + Send + 'static,
+    {
+        thread::spawn(move || {
+            let state = self.run_with(&mut input, &mut output);
+            (self, state)
+        })
+    }
+
+    /// Queues the character codes of `text` as input.
+    pub fn push_ascii(&mut self, text: &str) {
+        for code in ascii::encode(text) {
+            self.push_input(code);
+        }
+    }
+
+    /// Drains queued output, decoded as text where it is ASCII.
+    pub fn pop_ascii(&mut self) -> Vec<Ascii> {
+        let values: Vec<i64> = self.output.drain(..).collect();
+        ascii::decode(&values)
+    }
+
+    /// Takes output up to the next newline, running the machine if there is
+    /// not a whole line queued. The newline itself is dropped. If the machine
+    /// stops first, the partial line stays queued and the state is returned.
+    pub fn read_line(&mut self) -> Result<Vec<Ascii>, RunState> {
+        let mut line = Vec::new();
+
+        loop {
+            let value = match self.pop_output() {
+                Some(value) => value,
+                None => match self.run_until_output() {
+                    RunState::Output(value) => value,
+                    state => {
+                        self.output.extend(line);
+                        return Err(state);
+                    }
+                },
+            };
+            if value == '\n' as i64 {
+                return Ok(ascii::decode(&line));
+            }
+            line.push(value);
+        }
+    }
+
+    /// Restores a machine from `snapshot`. A snapshot holds only the program
+    /// state, so the memory limit, budget, history, profile and tracer start
+    /// out as on a new machine; set them again on the restored machine if
+    /// they are needed.
+    pub fn from_snapshot(snapshot: Snapshot) -> Machine {
+        let memory: Box<dyn Memory> = if snapshot.pages.is_empty() {
+            Box::new(DenseMemory::new(snapshot.memory))
+        } else {
+            let mut memory = PagedMemory::new(snapshot.memory);
+            for (start, values) in snapshot.pages {
+                for (offset, value) in values.into_iter().enumerate() {
+                    memory.write(start + offset, value);
+                }
+            }
+            Box::new(memory)
+        };
+
+        Machine {
+            decoded: vec![0; memory.allocated()],
+            memory,
+            ip: snapshot.ip,
+            relative_base: snapshot.relative_base,
+            input: snapshot.input.into_iter().collect(),
+            output: snapshot.output.into_iter().collect(),
+            last_write: None,
+            halted: snapshot.halted,
+            cycles: snapshot.cycles,
+            budget: None,
+            history: None,
+            profile: None,
+            tracer: None,
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let (memory, pages) = if self.memory.is_sparse() {
+            (Vec::new(), self.memory.segments())
+        } else {
+            (self.memory.segments().remove(0).1, Vec::new())
+        };
+
+        Snapshot {
+            memory,
+            pages,
+            ip: self.ip,
+            relative_base: self.relative_base,
+            input: self.input.iter().cloned().collect(),
+            output: self.output.iter().cloned().collect(),
+            cycles: self.cycles,
+            halted: self.halted,
+        }
+    }
+
+    pub fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::word::Word;
+
+pub const PAGE_SIZE: usize = 1024;
+
+/// The limit `DenseMemory` starts out with, so a write to a huge address
+/// faults instead of trying to allocate the whole range. Programs that need
+/// more should raise or remove the limit, or use `PagedMemory`.
+pub const DENSE_LIMIT: usize = 1 << 24;
+
+/// Storage for machine memory. Unwritten cells read as zero.
+pub trait Memory<W = i64>: Send {
+    fn read(&self, address: usize) -> W;
+
+    /// Returns false if the cell could not be allocated within the limit.
+    fn write(&mut self, address: usize, value: W) -> bool;
+
+    /// Number of cells backed by storage, which is what the limit applies to.
+    fn allocated(&self) -> usize;
+
+    /// One past the highest address loaded or written.
+    fn extent(&self) -> usize;
+
+    fn set_limit(&mut self, limit: Option<usize>);
+
+    /// Backed cells as `(start address, values)` runs in address order.
+    fn segments(&self) -> Vec<(usize, Vec<W>)>;
+
+    fn is_sparse(&self) -> bool {
+        false
+    }
+
+    fn clone_box(&self) -> Box<dyn Memory<W>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct DenseMemory<W = i64> {
+    cells: Vec<W>,
+    limit: Option<usize>,
+}
+
+impl<W: Word> DenseMemory<W> {
+    pub fn new(program: Vec<W>) -> DenseMemory<W> {
+        DenseMemory {
+            cells: program,
+            limit: Some(DENSE_LIMIT),
+        }
+    }
+}
+
+impl<W: Word> Default for DenseMemory<W> {
+    fn default() -> DenseMemory<W> {
+        DenseMemory::new(Vec::new())
+    }
+}
+
+impl<W: Word> Memory<W> for DenseMemory<W> {
+    fn read(&self, address: usize) -> W {
+        match self.cells.get(address) {
+            Some(value) => value.clone(),
+            None => W::default(),
+        }
+    }
+
+    fn write(&mut self, address: usize, value: W) -> bool {
+        if address >= self.cells.len() {
+            if self.limit.is_some_and(|limit| address >= limit) {
+                return false;
+            }
+            let length = match address.checked_add(1) {
+                Some(length) => length,
+                None => return false,
+            };
+            if self
+                .cells
+                .try_reserve_exact(length - self.cells.len())
+                .is_err()
+            {
+                return false;
+            }
+            self.cells.resize(length, W::default());
+        }
+
+        self.cells[address] = value;
+        true
+    }
+
+    fn allocated(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn extent(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    fn segments(&self) -> Vec<(usize, Vec<W>)> {
+        vec![(0, self.cells.clone())]
+    }
+
+    fn clone_box(&self) -> Box<dyn Memory<W>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Sparse memory made of fixed-size pages, allocated on first non-zero write.
+#[derive(Debug, Clone, Default)]
+pub struct PagedMemory<W = i64> {
+    pages: HashMap<usize, Box<[W]>>,
+    limit: Option<usize>,
+    /// One past the highest address loaded or written, zeros included, so a
+    /// program runs off its end where it would in `DenseMemory`.
+    extent: usize,
+}
+
+impl<W: Word> PagedMemory<W> {
+    pub fn new(program: Vec<W>) -> PagedMemory<W> {
+        let mut memory = PagedMemory {
+            pages: HashMap::new(),
+            limit: None,
+            extent: 0,
+        };
+
+        memory.extent = program.len();
+        for (address, value) in program.into_iter().enumerate() {
+            memory.write(address, value);
+        }
+        memory
+    }
+}
+
+impl<W: Word> Memory<W> for PagedMemory<W> {
+    fn read(&self, address: usize) -> W {
+        match self.pages.get(&(address / PAGE_SIZE)) {
+            Some(page) => page[address % PAGE_SIZE].clone(),
+            None => W::default(),
+        }
+    }
+
+    fn write(&mut self, address: usize, value: W) -> bool {
+        let index = address / PAGE_SIZE;
+
+        if !self.pages.contains_key(&index) {
+            if value == W::default() {
+                self.extent = self.extent.max(address.saturating_add(1));
+                return true;
+            }
+            if self
+                .limit
+                .is_some_and(|limit| self.allocated() + PAGE_SIZE > limit)
+            {
+                return false;
+            }
+            self.pages
+                .insert(index, vec![W::default(); PAGE_SIZE].into_boxed_slice());
+        }
+
+        self.pages.get_mut(&index).unwrap()[address % PAGE_SIZE] = value;
+        self.extent = self.extent.max(address.saturating_add(1));
+        true
+    }
+
+    fn allocated(&self) -> usize {
+        self.pages.len() * PAGE_SIZE
+    }
+
+    fn extent(&self) -> usize {
+        self.extent
+    }
+
+    fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    fn segments(&self) -> Vec<(usize, Vec<W>)> {
+        let mut indexes: Vec<&usize> = self.pages.keys().collect();
+        indexes.sort();
+
+        indexes
+            .into_iter()
+            .map(|index| (index * PAGE_SIZE, self.pages[index].to_vec()))
+            .collect()
+    }
+
+    fn is_sparse(&self) -> bool {
+        true
+    }
+
+    fn clone_box(&self) -> Box<dyn Memory<W>> {
+        Box::new(self.clone())
+    }
+}
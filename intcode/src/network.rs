@@ -0,0 +1,386 @@
+//! Scheduling for clusters of machines wired together by their I/O.
+
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+use crate::error::MachineError;
+use crate::machine::{Machine, RunState};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Topology {
+    /// Each machine feeds the next; the last machine's output leaves the network.
+    Pipeline,
+    /// A pipeline whose last machine also feeds the first.
+    Ring,
+    /// Machines send `destination, x, y` packets addressed by machine index and
+    /// read -1 when nothing is queued for them. Packets for other addresses
+    /// leave the network, except those for `nat`, which the NAT holds and
+    /// resends to machine 0 whenever the network goes idle.
+    Bus { nat: Option<i64> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    /// One machine at a time on the calling thread, each until it blocks.
+    RoundRobin,
+    /// Every machine on its own thread.
+    Threaded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Packet {
+    pub destination: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkState {
+    /// Every machine halted.
+    Halted,
+    /// Every machine still running is waiting for input nothing will send.
+    Deadlock {
+        waiting: Vec<usize>,
+    },
+    /// The bus went idle and the NAT had nothing new to send: either it never
+    /// received a packet or it just sent machine 0 the same packet twice.
+    Idle,
+    Faulted {
+        machine: usize,
+        error: MachineError,
+    },
+    BudgetExhausted {
+        machine: usize,
+    },
+}
+
+enum Receive {
+    Value(i64),
+    /// Nothing queued on the bus; the machine reads -1 and carries on.
+    Empty,
+    Wait,
+    Stop,
+}
+
+/// Routes values between machines and decides when the network is done.
+struct Router {
+    topology: Topology,
+    queues: Vec<VecDeque<i64>>,
+    packets: Vec<Vec<i64>>,
+    waiting: Vec<bool>,
+    stopped: Vec<bool>,
+    /// Empty bus reads by each machine since it last sent or received.
+    idle: Vec<u32>,
+    output: Vec<i64>,
+    outbound: Vec<Packet>,
+    nat: Option<Packet>,
+    nat_sent: Vec<Packet>,
+    state: Option<NetworkState>,
+}
+
+impl Router {
+    fn new(size: usize, topology: Topology) -> Router {
+        Router {
+            topology,
+            queues: vec![VecDeque::new(); size],
+            packets: vec![Vec::new(); size],
+            waiting: vec![false; size],
+            stopped: vec![false; size],
+            idle: vec![0; size],
+            output: Vec::new(),
+            outbound: Vec::new(),
+            nat: None,
+            nat_sent: Vec::new(),
+            state: None,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.queues.len()
+    }
+
+    fn send(&mut self, from: usize, value: i64) {
+        let size = self.size();
+
+        match self.topology {
+            Topology::Pipeline if from + 1 < size => self.queues[from + 1].push_back(value),
+            Topology::Pipeline => self.output.push(value),
+            Topology::Ring => {
+                self.queues[(from + 1) % size].push_back(value);
+                if from + 1 == size {
+                    self.output.push(value);
+                }
+            }
+            Topology::Bus { nat } => {
+                self.idle[from] = 0;
+                self.packets[from].push(value);
+                if self.packets[from].len() == 3 {
+                    let packet = Packet {
+                        destination: self.packets[from][0],
+                        x: self.packets[from][1],
+                        y: self.packets[from][2],
+                    };
+                    self.packets[from].clear();
+                    self.route(packet, nat);
+                }
+            }
+        }
+    }
+
+    fn route(&mut self, packet: Packet, nat: Option<i64>) {
+        if packet.destination >= 0 && (packet.destination as usize) < self.size() {
+            let queue = &mut self.queues[packet.destination as usize];
+            queue.push_back(packet.x);
+            queue.push_back(packet.y);
+        } else if Some(packet.destination) == nat {
+            self.nat = Some(packet);
+        } else {
+            self.outbound.push(packet);
+        }
+    }
+
+    fn receive(&mut self, machine: usize) -> Receive {
+        if self.state.is_some() {
+            return Receive::Stop;
+        }
+        if let Some(value) = self.queues[machine].pop_front() {
+            self.waiting[machine] = false;
+            self.idle[machine] = 0;
+            return Receive::Value(value);
+        }
+
+        if let Topology::Bus { .. } = self.topology {
+            self.idle[machine] += 1;
+            if self.is_idle() {
+                self.wake();
+            }
+            return match self.state {
+                Some(_) => Receive::Stop,
+                None => Receive::Empty,
+            };
+        }
+
+        self.waiting[machine] = true;
+        self.check_deadlock();
+        match self.state {
+            Some(_) => Receive::Stop,
+            None => Receive::Wait,
+        }
+    }
+
+    fn finish(&mut self, machine: usize, state: RunState) {
+        self.stopped[machine] = true;
+        self.waiting[machine] = false;
+
+        match state {
+            RunState::Faulted(error) => self.state = Some(NetworkState::Faulted { machine, error }),
+            RunState::BudgetExhausted => {
+                self.state = Some(NetworkState::BudgetExhausted { machine })
+            }
+            _ => self.check_deadlock(),
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        (0..self.size()).all(|machine| {
+            self.stopped[machine] || (self.idle[machine] >= 2 && self.queues[machine].is_empty())
+        })
+    }
+
+    /// Lets the NAT resend its packet to machine 0, stopping the network
+    /// instead if there is none or it repeats the previous one.
+    fn wake(&mut self) {
+        let packet = match self.nat {
+            Some(packet) => Packet {
+                destination: 0,
+                ..packet
+            },
+            None => {
+                self.state = Some(NetworkState::Idle);
+                return;
+            }
+        };
+
+        let repeated = self.nat_sent.last() == Some(&packet);
+        self.nat_sent.push(packet);
+        if repeated {
+            self.state = Some(NetworkState::Idle);
+            return;
+        }
+        self.queues[0].push_back(packet.x);
+        self.queues[0].push_back(packet.y);
+        for idle in self.idle.iter_mut() {
+            *idle = 0;
+        }
+    }
+
+    fn check_deadlock(&mut self) {
+        if self.state.is_some() {
+            return;
+        }
+        let stuck = (0..self.size()).all(|machine| {
+            self.stopped[machine] || (self.waiting[machine] && self.queues[machine].is_empty())
+        });
+        if !stuck {
+            return;
+        }
+
+        let waiting: Vec<usize> = (0..self.size())
+            .filter(|machine| !self.stopped[*machine])
+            .collect();
+        self.state = Some(if waiting.is_empty() {
+            NetworkState::Halted
+        } else {
+            NetworkState::Deadlock { waiting }
+        });
+    }
+}
+
+/// A set of machines connected by a topology. A network runs once; running it
+/// again returns the state it stopped in.
+pub struct Network {
+    machines: Vec<Machine>,
+    router: Router,
+}
+
+impl Network {
+    pub fn new(machines: Vec<Machine>, topology: Topology) -> Network {
+        Network {
+            router: Router::new(machines.len(), topology),
+            machines,
+        }
+    }
+
+    /// Queues a value on the network side of a machine's input, after any
+    /// input already pushed to the machine itself.
+    pub fn push_input(&mut self, machine: usize, value: i64) {
+        self.router.queues[machine].push_back(value);
+    }
+
+    pub fn run(&mut self, schedule: Schedule) -> NetworkState {
+        if self.machines.is_empty() {
+            return NetworkState::Halted;
+        }
+
+        match schedule {
+            Schedule::RoundRobin => self.run_round_robin(),
+            Schedule::Threaded => self.run_threaded(),
+        }
+        self.router.state.clone().unwrap()
+    }
+
+    fn run_round_robin(&mut self) {
+        let router = &mut self.router;
+
+        while router.state.is_none() {
+            for (index, machine) in self.machines.iter_mut().enumerate() {
+                if !router.stopped[index] {
+                    run_turn(index, machine, router);
+                }
+                if router.state.is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn run_threaded(&mut self) {
+        let router = Mutex::new(mem::replace(
+            &mut self.router,
+            Router::new(0, Topology::Pipeline),
+        ));
+        let wakeup = Condvar::new();
+
+        thread::scope(|scope| {
+            for (index, machine) in self.machines.iter_mut().enumerate() {
+                let (router, wakeup) = (&router, &wakeup);
+                scope.spawn(move || run_thread(index, machine, router, wakeup));
+            }
+        });
+        self.router = router.into_inner().unwrap();
+    }
+
+    /// Values that left the last machine of a pipeline or ring.
+    pub fn output(&self) -> &[i64] {
+        &self.router.output
+    }
+
+    /// Bus packets addressed to neither a machine nor the NAT.
+    pub fn outbound(&self) -> &[Packet] {
+        &self.router.outbound
+    }
+
+    /// Values queued on the network side of a machine's input.
+    pub fn pending(&self, machine: usize) -> &VecDeque<i64> {
+        &self.router.queues[machine]
+    }
+
+    /// Packets the NAT sent to machine 0, in order. The last one repeats the
+    /// one before it if that is what stopped the network, and was never
+    /// delivered.
+    pub fn nat_sent(&self) -> &[Packet] {
+        &self.router.nat_sent
+    }
+
+    pub fn machines(&self) -> &[Machine] {
+        &self.machines
+    }
+}
+
+/// Runs one machine until it blocks or stops.
+fn run_turn(index: usize, machine: &mut Machine, router: &mut Router) {
+    loop {
+        match machine.run_until_output() {
+            RunState::Output(value) => router.send(index, value),
+            RunState::NeedsInput => match router.receive(index) {
+                Receive::Value(value) => machine.push_input(value),
+                Receive::Empty => {
+                    machine.push_input(-1);
+                    return;
+                }
+                Receive::Wait | Receive::Stop => return,
+            },
+            state => return router.finish(index, state),
+        }
+    }
+}
+
+fn run_thread(index: usize, machine: &mut Machine, router: &Mutex<Router>, wakeup: &Condvar) {
+    loop {
+        match machine.run_until_output() {
+            RunState::Output(value) => {
+                router.lock().unwrap().send(index, value);
+                wakeup.notify_all();
+            }
+            RunState::NeedsInput => {
+                let mut guard = router.lock().unwrap();
+                loop {
+                    match guard.receive(index) {
+                        Receive::Value(value) => {
+                            machine.push_input(value);
+                            break;
+                        }
+                        Receive::Empty => {
+                            machine.push_input(-1);
+                            drop(guard);
+                            thread::yield_now();
+                            break;
+                        }
+                        Receive::Wait => guard = wakeup.wait(guard).unwrap(),
+                        Receive::Stop => {
+                            wakeup.notify_all();
+                            return;
+                        }
+                    }
+                }
+            }
+            state => {
+                router.lock().unwrap().finish(index, state);
+                wakeup.notify_all();
+                return;
+            }
+        }
+    }
+}
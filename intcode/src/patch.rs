@@ -0,0 +1,248 @@
+//! Memory patches applied to a program before it runs.
+//!
+//! A patch file holds one poke per line, `address = value`, where a comma
+//! separated list of values fills consecutive cells. Blank lines are ignored
+//! and `#` starts a comment:
+//!
+//! ```text
+//! # free play
+//! 0 = 2
+//! 1 = 12, 2
+//! ```
+//!
+//! Binaries take the same pokes on the command line as `--poke 0=2`, and
+//! whole files as `--patch <file>`. The other command line helpers the
+//! binaries share live here too.
+
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::process;
+use std::str::FromStr;
+
+/// Values written to consecutive cells starting at `address`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Poke {
+    pub address: usize,
+    pub values: Vec<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    /// A poke that is not `address = value[, value...]`. `line` is zero for
+    /// pokes given on the command line.
+    InvalidPoke {
+        line: usize,
+        text: String,
+    },
+    /// A poke writing past the end of the program; `address` is the first
+    /// poked cell outside it.
+    OutOfRange {
+        address: usize,
+        length: usize,
+    },
+    MissingArgument {
+        option: String,
+    },
+    Io {
+        filepath: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::InvalidPoke { line: 0, text } => write!(f, "invalid poke `{}`", text),
+            PatchError::InvalidPoke { line, text } => {
+                write!(f, "invalid poke `{}` on line {}", text, line)
+            }
+            PatchError::OutOfRange { address, length } => write!(
+                f,
+                "poke at {} is outside the program of length {}",
+                address, length
+            ),
+            PatchError::MissingArgument { option } => write!(f, "missing argument for {}", option),
+            PatchError::Io { filepath, message } => write!(f, "{}: {}", filepath, message),
+        }
+    }
+}
+
+impl Error for PatchError {}
+
+fn parse_poke(text: &str, line: usize) -> Result<Poke, PatchError> {
+    let invalid = || PatchError::InvalidPoke {
+        line,
+        text: text.to_string(),
+    };
+    let (address, values) = match text.split_once('=') {
+        Some(parts) => parts,
+        None => return Err(invalid()),
+    };
+
+    let address = address.trim().parse().map_err(|_| invalid())?;
+    let values = values
+        .split(',')
+        .map(|value| value.trim().parse().map_err(|_| invalid()))
+        .collect::<Result<Vec<i64>, PatchError>>()?;
+    Ok(Poke { address, values })
+}
+
+/// An ordered list of pokes; later pokes win where they overlap.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Patch {
+    pokes: Vec<Poke>,
+}
+
+impl Patch {
+    pub fn new() -> Patch {
+        Patch::default()
+    }
+
+    /// Parses the patch file format.
+    pub fn parse(contents: &str) -> Result<Patch, PatchError> {
+        let mut patch = Patch::new();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line_text = match line.find('#') {
+                Some(comment) => &line[..comment],
+                None => line,
+            };
+            if line_text.trim().is_empty() {
+                continue;
+            }
+            patch.pokes.push(parse_poke(line_text.trim(), index + 1)?);
+        }
+
+        Ok(patch)
+    }
+
+    pub fn load(filepath: &str) -> Result<Patch, PatchError> {
+        let contents = fs::read_to_string(filepath).map_err(|error| PatchError::Io {
+            filepath: filepath.to_string(),
+            message: error.to_string(),
+        })?;
+        Patch::parse(&contents)
+    }
+
+    /// Adds a poke given as `address=value[,value...]`.
+    pub fn add_poke(&mut self, text: &str) -> Result<(), PatchError> {
+        self.pokes.push(parse_poke(text, 0)?);
+        Ok(())
+    }
+
+    pub fn poke(&mut self, address: usize, values: &[i64]) {
+        self.pokes.push(Poke {
+            address,
+            values: values.to_vec(),
+        });
+    }
+
+    /// Appends the pokes of `other`, which then win over these.
+    pub fn extend(&mut self, other: Patch) {
+        self.pokes.extend(other.pokes);
+    }
+
+    pub fn pokes(&self) -> &[Poke] {
+        &self.pokes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pokes.is_empty()
+    }
+
+    /// Checks every poke lands inside a program of `length` cells. A poke
+    /// without values writes nothing and always fits.
+    pub fn validate(&self, length: usize) -> Result<(), PatchError> {
+        for poke in self.pokes.iter() {
+            if poke.values.is_empty() {
+                continue;
+            }
+            let end = poke.address.checked_add(poke.values.len());
+            if end.is_none_or(|end| end > length) {
+                return Err(PatchError::OutOfRange {
+                    address: poke.address.max(length),
+                    length,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the pokes into `program`, leaving it untouched if any is out of
+    /// range.
+    pub fn apply(&self, program: &mut [i64]) -> Result<(), PatchError> {
+        self.validate(program.len())?;
+        for poke in self.pokes.iter().filter(|poke| !poke.values.is_empty()) {
+            program[poke.address..poke.address + poke.values.len()].copy_from_slice(&poke.values);
+        }
+        Ok(())
+    }
+
+    /// A patched copy of `program`.
+    pub fn variant(&self, program: &[i64]) -> Result<Vec<i64>, PatchError> {
+        let mut variant = program.to_vec();
+        self.apply(&mut variant)?;
+        Ok(variant)
+    }
+
+    /// Like `variant`, but patches `program` in place of copying it.
+    pub fn patched(&self, mut program: Vec<i64>) -> Result<Vec<i64>, PatchError> {
+        self.apply(&mut program)?;
+        Ok(program)
+    }
+
+    /// Reads the program at `filepath` and patches it, reporting a poke past
+    /// its end and exiting, as the binaries do.
+    pub fn load_program(&self, filepath: &str) -> Vec<i64> {
+        self.patched(crate::tokenize(filepath))
+            .unwrap_or_else(|error| exit_with(&error.to_string()))
+    }
+}
+
+/// Reports `message` on stderr and exits with status 1.
+pub fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+/// Parses a command line value, exiting if it is missing or invalid.
+pub fn parse<T: FromStr>(value: Option<&String>, name: &str) -> T {
+    match value.map(|value| value.parse()) {
+        Some(Ok(value)) => value,
+        _ => exit_with(&format!("Invalid value for {}", name)),
+    }
+}
+
+/// Takes `--poke <address=value>` and `--patch <file>` options out of `args`,
+/// returning the remaining arguments and the patch they describe, in command
+/// line order.
+pub fn split_args(args: Vec<String>) -> Result<(Vec<String>, Patch), PatchError> {
+    let mut rest = Vec::new();
+    let mut patch = Patch::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg != "--poke" && arg != "--patch" {
+            rest.push(arg);
+            continue;
+        }
+        let value = match args.next() {
+            Some(value) => value,
+            None => return Err(PatchError::MissingArgument { option: arg }),
+        };
+        if arg == "--poke" {
+            patch.add_poke(&value)?;
+        } else {
+            patch.extend(Patch::load(&value)?);
+        }
+    }
+
+    Ok((rest, patch))
+}
+
+/// `split_args` on the process arguments, reporting a bad option and exiting.
+pub fn from_env() -> (Vec<String>, Patch) {
+    split_args(env::args().collect()).unwrap_or_else(|error| exit_with(&error.to_string()))
+}
@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::decode::{self, Instruction};
+use crate::disassembler::{self, Line};
+use crate::trace::Operand;
+
+/// A backward jump taken during the run, covering `start..=end`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loop {
+    pub start: usize,
+    pub end: usize,
+    /// Times the backward jump was taken.
+    pub iterations: u64,
+    /// Instructions executed at addresses inside the loop.
+    pub instructions: u64,
+}
+
+/// Execution counts collected while a machine runs. Instructions that block
+/// on input or fault are not counted, and a halt is counted once however
+/// often the halted machine is run again.
+///
+/// Call stacks for `folded` are guessed from the relative base: raising it
+/// enters a frame named after the `arb` that did so, lowering it leaves the
+/// innermost frame.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    executions: BTreeMap<usize, u64>,
+    operations: BTreeMap<i64, u64>,
+    reads: BTreeMap<usize, u64>,
+    writes: BTreeMap<usize, u64>,
+    max_address: usize,
+    back_edges: BTreeMap<(usize, usize), u64>,
+    stack: Vec<usize>,
+    stack_ids: HashMap<Vec<usize>, usize>,
+    samples: Vec<u64>,
+    current: usize,
+}
+
+impl Default for Profile {
+    fn default() -> Profile {
+        Profile::new()
+    }
+}
+
+impl Profile {
+    pub fn new() -> Profile {
+        let mut stack_ids = HashMap::new();
+        stack_ids.insert(Vec::new(), 0);
+
+        Profile {
+            executions: BTreeMap::new(),
+            operations: BTreeMap::new(),
+            reads: BTreeMap::new(),
+            writes: BTreeMap::new(),
+            max_address: 0,
+            back_edges: BTreeMap::new(),
+            stack: Vec::new(),
+            stack_ids,
+            samples: vec![0],
+            current: 0,
+        }
+    }
+
+    fn touch(&mut self, address: usize) {
+        self.max_address = self.max_address.max(address);
+    }
+
+    fn enter_stack(&mut self) {
+        let next = self.stack_ids.len();
+        self.current = *self.stack_ids.entry(self.stack.clone()).or_insert(next);
+        if self.current == self.samples.len() {
+            self.samples.push(0);
+        }
+    }
+
+    /// Records one executed instruction. `operands` are its parameters as
+    /// resolved before it ran, `next_ip` and `relative_base` the state after.
+    pub(crate) fn record(
+        &mut self,
+        ip: usize,
+        instruction: &Instruction,
+        operands: &[Operand],
+        write: Option<usize>,
+        next_ip: usize,
+        relative_base: (i64, i64),
+    ) {
+        *self.executions.entry(ip).or_insert(0) += 1;
+        *self.operations.entry(instruction.op).or_insert(0) += 1;
+        self.touch(ip + instruction.size - 1);
+
+        let write_parameter = decode::get_write_parameter(instruction.op);
+        for (index, operand) in operands.iter().enumerate() {
+            if write_parameter != Some(index) && instruction.addressing[index] != 1 {
+                *self.reads.entry(operand.address).or_insert(0) += 1;
+                self.touch(operand.address);
+            }
+        }
+        if let Some(address) = write {
+            *self.writes.entry(address).or_insert(0) += 1;
+            self.touch(address);
+        }
+
+        let jumped = next_ip != ip + instruction.size;
+        if (instruction.op == 5 || instruction.op == 6) && jumped && next_ip <= ip {
+            *self.back_edges.entry((next_ip, ip)).or_insert(0) += 1;
+        }
+
+        let (before, after) = relative_base;
+        if after > before {
+            self.stack.push(ip);
+            self.enter_stack();
+        }
+        self.samples[self.current] += 1;
+        if after < before && self.stack.pop().is_some() {
+            self.enter_stack();
+        }
+    }
+
+    /// Executions per instruction address.
+    pub fn executions(&self) -> &BTreeMap<usize, u64> {
+        &self.executions
+    }
+
+    /// Executions per operation, with halt as 99.
+    pub fn operations(&self) -> &BTreeMap<i64, u64> {
+        &self.operations
+    }
+
+    /// Parameter reads per address. Fetching the instruction and its
+    /// immediate parameters does not count.
+    pub fn reads(&self) -> &BTreeMap<usize, u64> {
+        &self.reads
+    }
+
+    pub fn writes(&self) -> &BTreeMap<usize, u64> {
+        &self.writes
+    }
+
+    /// The highest address executed, read or written.
+    pub fn max_address(&self) -> usize {
+        self.max_address
+    }
+
+    pub fn total(&self) -> u64 {
+        self.executions.values().sum()
+    }
+
+    /// Loops entered by backward jumps, busiest first.
+    pub fn hot_loops(&self) -> Vec<Loop> {
+        let mut loops: Vec<Loop> = self
+            .back_edges
+            .iter()
+            .map(|((start, end), iterations)| Loop {
+                start: *start,
+                end: *end,
+                iterations: *iterations,
+                instructions: self
+                    .executions
+                    .range(*start..=*end)
+                    .map(|(_, count)| count)
+                    .sum(),
+            })
+            .collect();
+
+        loops.sort_by(|lhs, rhs| {
+            rhs.instructions
+                .cmp(&lhs.instructions)
+                .then(lhs.start.cmp(&rhs.start))
+        });
+        loops
+    }
+
+    /// The disassembly of `program` with execution counts in the margin,
+    /// followed by a coverage summary. Instructions never executed are marked
+    /// with `-`; data lines show the executions at the addresses they cover.
+    pub fn annotate(&self, program: &[i64]) -> String {
+        let mut text = String::new();
+        let (mut instructions, mut covered) = (0, 0);
+
+        for line in disassembler::disassemble(program) {
+            let (address, size) = match &line {
+                Line::Instruction {
+                    address,
+                    instruction,
+                    ..
+                } => (*address, instruction.size),
+                Line::Data {
+                    address, values, ..
+                } => (*address, values.len()),
+            };
+            let count: u64 = self
+                .executions
+                .range(address..address + size)
+                .map(|(_, count)| count)
+                .sum();
+
+            let margin = match line {
+                Line::Instruction { .. } => {
+                    instructions += 1;
+                    if count > 0 {
+                        covered += 1;
+                        count.to_string()
+                    } else {
+                        "-".to_string()
+                    }
+                }
+                Line::Data { .. } if count > 0 => count.to_string(),
+                Line::Data { .. } => String::new(),
+            };
+            writeln!(text, "{:>10} |{}", margin, line).unwrap();
+        }
+
+        writeln!(
+            text,
+            "coverage: {} of {} instructions executed",
+            covered, instructions
+        )
+        .unwrap();
+        text
+    }
+
+    /// Instructions executed per guessed call stack, one `frame;frame count`
+    /// line each, as taken by flamegraph tools.
+    pub fn folded(&self) -> String {
+        let mut stacks: Vec<(String, u64)> = self
+            .stack_ids
+            .iter()
+            .filter(|(_, id)| self.samples[**id] > 0)
+            .map(|(stack, id)| {
+                let mut frames = vec!["main".to_string()];
+                frames.extend(stack.iter().map(|ip| format!("sub_{}", ip)));
+                (frames.join(";"), self.samples[*id])
+            })
+            .collect();
+        stacks.sort();
+
+        let mut text = String::new();
+        for (stack, count) in stacks {
+            writeln!(text, "{} {}", stack, count).unwrap();
+        }
+        text
+    }
+
+    /// Totals, per-operation counts, the busiest addresses and hot loops.
+    pub fn summary(&self, top: usize) -> String {
+        let mut text = String::new();
+
+        writeln!(text, "instructions: {}", self.total()).unwrap();
+        writeln!(text, "max address: {}", self.max_address).unwrap();
+        writeln!(text, "operations:").unwrap();
+        for (op, count) in self.operations.iter() {
+            let mnemonic = decode::get_mnemonic(*op).unwrap_or("?");
+            writeln!(text, "  {:<4} {:>10}", mnemonic, count).unwrap();
+        }
+
+        for (name, counts) in [("reads", &self.reads), ("writes", &self.writes)].iter() {
+            let mut busiest: Vec<(&usize, &u64)> = counts.iter().collect();
+            busiest.sort_by(|lhs, rhs| rhs.1.cmp(lhs.1).then(lhs.0.cmp(rhs.0)));
+            writeln!(text, "{} ({} addresses):", name, counts.len()).unwrap();
+            for (address, count) in busiest.into_iter().take(top) {
+                writeln!(text, "  [{}] {:>10}", address, count).unwrap();
+            }
+        }
+
+        writeln!(text, "hot loops:").unwrap();
+        for found in self.hot_loops().into_iter().take(top) {
+            writeln!(
+                text,
+                "  {}..={} {:>10} instructions, {} iterations",
+                found.start, found.end, found.instructions, found.iterations
+            )
+            .unwrap();
+        }
+        text
+    }
+}
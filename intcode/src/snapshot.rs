@@ -0,0 +1,34 @@
+use std::fs;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+/// The program state of a machine: memory, registers, queues, the cycle
+/// count and whether it has halted. Settings such as the memory limit and
+/// budget are not included.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub memory: Vec<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<(usize, Vec<i64>)>,
+    pub ip: usize,
+    pub relative_base: i64,
+    pub input: Vec<i64>,
+    pub output: Vec<i64>,
+    #[serde(default)]
+    pub cycles: u64,
+    #[serde(default)]
+    pub halted: bool,
+}
+
+impl Snapshot {
+    pub fn save(&self, filepath: &str) -> io::Result<()> {
+        let contents = serde_json::to_string(self).map_err(io::Error::from)?;
+        fs::write(filepath, contents)
+    }
+
+    pub fn load(filepath: &str) -> io::Result<Snapshot> {
+        let contents = fs::read_to_string(filepath)?;
+        serde_json::from_str(&contents).map_err(io::Error::from)
+    }
+}
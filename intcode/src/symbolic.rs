@@ -0,0 +1,602 @@
+//! Symbolic evaluation of Intcode programs with some memory cells left as
+//! unknowns, for inverting programs such as the day 2 noun/verb puzzle.
+//!
+//! Values are polynomials in the unknowns. A read through an address that
+//! depends on the unknowns gives an opaque `Cell::Unknown`, which is fine as
+//! long as it is overwritten before it matters. Opcodes, jumps, relative base
+//! changes and write addresses have to stay concrete; when they do not,
+//! `solve` falls back to running the program for every candidate.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::decode;
+use crate::error::MachineError;
+use crate::machine::{Machine, RunState};
+use crate::memory::DENSE_LIMIT;
+
+/// Instructions a symbolic run may execute before giving up.
+const STEP_LIMIT: u64 = 1_000_000;
+
+/// A polynomial in the unknowns with integer coefficients. Each term maps the
+/// sorted indices of its unknowns, repeated for powers, to a coefficient.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Expr {
+    terms: BTreeMap<Vec<usize>, i64>,
+}
+
+impl Expr {
+    pub fn constant(value: i64) -> Expr {
+        let mut terms = BTreeMap::new();
+        if value != 0 {
+            terms.insert(Vec::new(), value);
+        }
+        Expr { terms }
+    }
+
+    pub fn unknown(index: usize) -> Expr {
+        let mut terms = BTreeMap::new();
+        terms.insert(vec![index], 1);
+        Expr { terms }
+    }
+
+    pub fn as_constant(&self) -> Option<i64> {
+        match self.terms.len() {
+            0 => Some(0),
+            1 => self.terms.get(&Vec::new()).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Coefficient of the term made of exactly `unknowns`, sorted.
+    pub fn coefficient(&self, unknowns: &[usize]) -> i64 {
+        self.terms.get(unknowns).cloned().unwrap_or(0)
+    }
+
+    fn insert(terms: &mut BTreeMap<Vec<usize>, i64>, key: Vec<usize>, value: i64) -> Option<()> {
+        let sum = terms.get(&key).unwrap_or(&0).checked_add(value)?;
+        if sum == 0 {
+            terms.remove(&key);
+        } else {
+            terms.insert(key, sum);
+        }
+        Some(())
+    }
+
+    /// `None` on coefficient overflow.
+    pub fn add(&self, other: &Expr) -> Option<Expr> {
+        let mut terms = self.terms.clone();
+        for (key, value) in other.terms.iter() {
+            Expr::insert(&mut terms, key.clone(), *value)?;
+        }
+        Some(Expr { terms })
+    }
+
+    /// `None` on coefficient overflow.
+    pub fn multiply(&self, other: &Expr) -> Option<Expr> {
+        let mut terms = BTreeMap::new();
+        for (lhs, lhs_value) in self.terms.iter() {
+            for (rhs, rhs_value) in other.terms.iter() {
+                let mut key: Vec<usize> = lhs.iter().chain(rhs.iter()).cloned().collect();
+                key.sort_unstable();
+                Expr::insert(&mut terms, key, lhs_value.checked_mul(*rhs_value)?)?;
+            }
+        }
+        Some(Expr { terms })
+    }
+
+    /// The unknowns the expression depends on.
+    pub fn unknowns(&self) -> Vec<usize> {
+        let mut unknowns: Vec<usize> = self.terms.keys().flatten().cloned().collect();
+        unknowns.sort_unstable();
+        unknowns.dedup();
+        unknowns
+    }
+
+    /// `None` on overflow.
+    pub fn evaluate(&self, values: &[i64]) -> Option<i64> {
+        self.terms
+            .iter()
+            .try_fold(0_i64, |sum, (key, coefficient)| {
+                let term = key.iter().try_fold(*coefficient, |product, index| {
+                    product.checked_mul(values[*index])
+                })?;
+                sum.checked_add(term)
+            })
+    }
+
+    /// Splits the expression into `q` and `r` with `self = unknown * q + r`,
+    /// if `unknown` appears at most linearly.
+    fn split(&self, unknown: usize) -> Option<(Expr, Expr)> {
+        let mut quotient = Expr::default();
+        let mut remainder = Expr::default();
+
+        for (key, value) in self.terms.iter() {
+            match key.iter().filter(|index| **index == unknown).count() {
+                0 => {
+                    remainder.terms.insert(key.clone(), *value);
+                }
+                1 => {
+                    let rest: Vec<usize> = key
+                        .iter()
+                        .filter(|index| **index != unknown)
+                        .cloned()
+                        .collect();
+                    quotient.terms.insert(rest, *value);
+                }
+                _ => return None,
+            }
+        }
+        Some((quotient, remainder))
+    }
+
+    /// Formats the expression with `names` for the unknowns, highest degree
+    /// first and the constant last.
+    pub fn format(&self, names: &[String]) -> String {
+        let mut terms: Vec<(&Vec<usize>, &i64)> = self.terms.iter().collect();
+        terms.sort_by(|lhs, rhs| rhs.0.len().cmp(&lhs.0.len()).then(lhs.0.cmp(rhs.0)));
+        if terms.is_empty() {
+            return "0".to_string();
+        }
+
+        let mut text = String::new();
+        for (position, (key, coefficient)) in terms.into_iter().enumerate() {
+            let magnitude = coefficient.unsigned_abs();
+            match (position, *coefficient < 0) {
+                (0, true) => text.push('-'),
+                (0, false) => {}
+                (_, true) => text.push_str(" - "),
+                (_, false) => text.push_str(" + "),
+            }
+
+            let mut factors: Vec<String> = key.iter().map(|index| names[*index].clone()).collect();
+            if magnitude != 1 || factors.is_empty() {
+                factors.insert(0, magnitude.to_string());
+            }
+            text.push_str(&factors.join("*"));
+        }
+        text
+    }
+}
+
+/// The symbolic contents of a memory cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Known(Expr),
+    /// Depends on the unknowns in a way an `Expr` cannot express.
+    Unknown,
+}
+
+impl Cell {
+    fn constant(&self) -> Option<i64> {
+        match self {
+            Cell::Known(expr) => expr.as_constant(),
+            Cell::Unknown => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolicError {
+    /// The opcode, a jump or a relative base change depends on the unknowns.
+    SymbolicControl {
+        ip: usize,
+    },
+    /// A write address depends on the unknowns.
+    SymbolicAddress {
+        ip: usize,
+    },
+    /// The requested cell does not reduce to an `Expr`.
+    Unresolved {
+        address: usize,
+    },
+    NeedsInput {
+        ip: usize,
+    },
+    StepLimit,
+    Fault(MachineError),
+}
+
+impl fmt::Display for SymbolicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymbolicError::SymbolicControl { ip } => {
+                write!(f, "control flow depends on the unknowns at ip {}", ip)
+            }
+            SymbolicError::SymbolicAddress { ip } => {
+                write!(f, "write address depends on the unknowns at ip {}", ip)
+            }
+            SymbolicError::Unresolved { address } => {
+                write!(f, "value at {} depends on unresolved reads", address)
+            }
+            SymbolicError::NeedsInput { ip } => write!(f, "input requested at ip {}", ip),
+            SymbolicError::StepLimit => write!(f, "step limit of {} reached", STEP_LIMIT),
+            SymbolicError::Fault(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for SymbolicError {}
+
+/// A memory cell replaced by an unknown taking values in `range`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub address: usize,
+    pub name: String,
+    pub range: RangeInclusive<i64>,
+}
+
+/// A program with some of its cells replaced by unknowns.
+pub struct Symbolic {
+    program: Vec<i64>,
+    symbols: Vec<Symbol>,
+}
+
+/// Memory and instruction pointer of a symbolic run.
+struct State {
+    memory: Vec<Cell>,
+    ip: usize,
+    relative_base: i64,
+}
+
+impl State {
+    fn read(&self, address: usize) -> Cell {
+        match self.memory.get(address) {
+            Some(cell) => cell.clone(),
+            None => Cell::Known(Expr::default()),
+        }
+    }
+
+    /// Stores `cell`, growing memory up to the machine's default limit.
+    fn write(&mut self, address: usize, cell: Cell) -> Result<(), SymbolicError> {
+        if address >= DENSE_LIMIT {
+            return Err(SymbolicError::Fault(MachineError::MemoryLimitExceeded {
+                ip: self.ip,
+                address,
+            }));
+        }
+        if address >= self.memory.len() {
+            self.memory
+                .resize(address + 1, Cell::Known(Expr::default()));
+        }
+        self.memory[address] = cell;
+        Ok(())
+    }
+
+    /// The address parameter `index` refers to, or `None` if it depends on
+    /// the unknowns.
+    fn address(&self, mode: i64, index: usize) -> Result<Option<usize>, SymbolicError> {
+        let ip = self.ip;
+        let position = ip + 1 + index;
+        let address = match mode {
+            0 => match self.read(position).constant() {
+                Some(address) => address,
+                None => return Ok(None),
+            },
+            1 => return Ok(Some(position)),
+            _ => match self.read(position).constant() {
+                Some(offset) => match self.relative_base.checked_add(offset) {
+                    Some(address) => address,
+                    None => return Err(SymbolicError::Fault(MachineError::AddressOverflow { ip })),
+                },
+                None => return Ok(None),
+            },
+        };
+        if address < 0 {
+            return Err(SymbolicError::Fault(MachineError::NegativeAddress {
+                ip,
+                address,
+            }));
+        }
+        Ok(Some(address as usize))
+    }
+
+    fn value(&self, mode: i64, index: usize) -> Result<Cell, SymbolicError> {
+        Ok(match self.address(mode, index)? {
+            Some(address) => self.read(address),
+            None => Cell::Unknown,
+        })
+    }
+
+    fn constant(&self, mode: i64, index: usize) -> Result<i64, SymbolicError> {
+        self.value(mode, index)?
+            .constant()
+            .ok_or(SymbolicError::SymbolicControl { ip: self.ip })
+    }
+
+    fn store(&mut self, mode: i64, index: usize, cell: Cell) -> Result<(), SymbolicError> {
+        if mode == 1 {
+            return Err(SymbolicError::Fault(MachineError::WriteToImmediate {
+                ip: self.ip,
+            }));
+        }
+        match self.address(mode, index)? {
+            Some(address) => self.write(address, cell),
+            None => Err(SymbolicError::SymbolicAddress { ip: self.ip }),
+        }
+    }
+
+    fn jump(&mut self, target: i64) -> Result<(), SymbolicError> {
+        if target < 0 {
+            return Err(SymbolicError::Fault(MachineError::IpOutOfRange {
+                ip: target,
+            }));
+        }
+        self.ip = target as usize;
+        Ok(())
+    }
+
+    fn arithmetic(&self, op: i64, lhs: Cell, rhs: Cell) -> Result<Cell, SymbolicError> {
+        let (lhs, rhs) = match (lhs, rhs) {
+            (Cell::Known(lhs), Cell::Known(rhs)) => (lhs, rhs),
+            _ => return Ok(Cell::Unknown),
+        };
+        let result = match op {
+            1 => lhs.add(&rhs),
+            _ => lhs.multiply(&rhs),
+        };
+        match result {
+            Some(expr) => Ok(Cell::Known(expr)),
+            None => Err(SymbolicError::Fault(MachineError::ArithmeticOverflow {
+                ip: self.ip,
+            })),
+        }
+    }
+
+    fn compare(op: i64, lhs: Cell, rhs: Cell) -> Cell {
+        match (lhs.constant(), rhs.constant()) {
+            (Some(lhs), Some(rhs)) if op == 7 => Cell::Known(Expr::constant((lhs < rhs) as i64)),
+            (Some(lhs), Some(rhs)) => Cell::Known(Expr::constant((lhs == rhs) as i64)),
+            _ if op == 8 && lhs == rhs && lhs != Cell::Unknown => Cell::Known(Expr::constant(1)),
+            _ => Cell::Unknown,
+        }
+    }
+
+    /// Executes one instruction, returning false on halt.
+    fn step(&mut self) -> Result<bool, SymbolicError> {
+        let ip = self.ip;
+        let opcode = self
+            .read(ip)
+            .constant()
+            .ok_or(SymbolicError::SymbolicControl { ip })?;
+        if opcode == decode::HALT {
+            return Ok(false);
+        }
+        let op = opcode % 100;
+        let size = match decode::get_operation_size(op) {
+            Some(size) => size,
+            None => {
+                return Err(SymbolicError::Fault(MachineError::UnknownOperation {
+                    ip,
+                    value: opcode,
+                }))
+            }
+        };
+        let modes = decode::get_addressing(opcode / 100, size)
+            .map_err(|mode| SymbolicError::Fault(MachineError::UnknownAddressing { ip, mode }))?;
+
+        match op {
+            1 | 2 => {
+                let result =
+                    self.arithmetic(op, self.value(modes[0], 0)?, self.value(modes[1], 1)?)?;
+                self.store(modes[2], 2, result)?;
+            }
+            3 => return Err(SymbolicError::NeedsInput { ip }),
+            4 => {
+                self.value(modes[0], 0)?;
+            }
+            5 | 6 => {
+                let condition = self.constant(modes[0], 0)?;
+                if (condition != 0) == (op == 5) {
+                    let target = self.constant(modes[1], 1)?;
+                    return self.jump(target).map(|_| true);
+                }
+            }
+            7 | 8 => {
+                let result = State::compare(op, self.value(modes[0], 0)?, self.value(modes[1], 1)?);
+                self.store(modes[2], 2, result)?;
+            }
+            _ => {
+                let adjustment = self.constant(modes[0], 0)?;
+                self.relative_base = self
+                    .relative_base
+                    .checked_add(adjustment)
+                    .ok_or(SymbolicError::Fault(MachineError::AddressOverflow { ip }))?;
+            }
+        }
+        self.ip += size;
+        Ok(true)
+    }
+}
+
+impl Symbolic {
+    pub fn new(program: &[i64]) -> Symbolic {
+        Symbolic {
+            program: program.to_vec(),
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Replaces the cell at `address` with an unknown called `name`.
+    pub fn add_symbol(&mut self, address: usize, name: &str, range: RangeInclusive<i64>) {
+        self.symbols.push(Symbol {
+            address,
+            name: name.to_string(),
+            range,
+        });
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.symbols
+            .iter()
+            .map(|symbol| symbol.name.clone())
+            .collect()
+    }
+
+    /// Runs the program symbolically until it halts and returns the contents
+    /// of memory.
+    pub fn run(&self) -> Result<Vec<Cell>, SymbolicError> {
+        let mut state = State {
+            memory: self
+                .program
+                .iter()
+                .map(|value| Cell::Known(Expr::constant(*value)))
+                .collect(),
+            ip: 0,
+            relative_base: 0,
+        };
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            state.write(symbol.address, Cell::Known(Expr::unknown(index)))?;
+        }
+
+        for _ in 0..STEP_LIMIT {
+            if !state.step()? {
+                return Ok(state.memory);
+            }
+        }
+        Err(SymbolicError::StepLimit)
+    }
+
+    /// The value left at `address` once the program halts, in terms of the
+    /// unknowns.
+    pub fn evaluate(&self, address: usize) -> Result<Expr, SymbolicError> {
+        match self.run()?.get(address) {
+            Some(Cell::Known(expr)) => Ok(expr.clone()),
+            Some(Cell::Unknown) => Err(SymbolicError::Unresolved { address }),
+            None => Ok(Expr::default()),
+        }
+    }
+
+    /// Whether running the program concretely with `values` leaves `target`
+    /// at `address`.
+    pub fn check(&self, values: &[i64], address: usize, target: i64) -> bool {
+        let mut program = self.program.clone();
+        for (symbol, value) in self.symbols.iter().zip(values.iter()) {
+            if symbol.address >= program.len() {
+                program.resize(symbol.address + 1, 0);
+            }
+            program[symbol.address] = *value;
+        }
+
+        let mut machine = Machine::new(program);
+        machine.set_budget(Some(STEP_LIMIT));
+        machine.execute_program() == RunState::Halted && machine.read_memory(address) == target
+    }
+
+    /// Every assignment of the symbols in `symbols`, in lexicographic order.
+    fn assignments(&self, symbols: &[usize], mut visit: impl FnMut(&mut Vec<i64>)) {
+        let mut values: Vec<i64> = self
+            .symbols
+            .iter()
+            .map(|symbol| *symbol.range.start())
+            .collect();
+        if self.symbols.iter().any(|symbol| symbol.range.is_empty()) {
+            return;
+        }
+
+        loop {
+            visit(&mut values);
+
+            let mut position = symbols.len();
+            loop {
+                if position == 0 {
+                    return;
+                }
+                position -= 1;
+                let symbol = &self.symbols[symbols[position]];
+                if values[symbols[position]] < *symbol.range.end() {
+                    values[symbols[position]] += 1;
+                    break;
+                }
+                values[symbols[position]] = *symbol.range.start();
+            }
+        }
+    }
+
+    /// Tries every assignment of the symbols on the concrete machine and
+    /// returns the first, in lexicographic order, leaving `target` at
+    /// `address`.
+    pub fn search(&self, address: usize, target: i64) -> Option<Vec<i64>> {
+        let all: Vec<usize> = (0..self.symbols.len()).collect();
+        let mut found = None;
+        self.assignments(&all, |values| {
+            if found.is_none() && self.check(values, address, target) {
+                found = Some(values.clone());
+            }
+        });
+        found
+    }
+
+    /// Candidate solutions of `expr == target`. An unknown appearing only
+    /// linearly is solved for directly while the rest are enumerated.
+    fn candidates(&self, expr: &Expr, target: i64) -> Vec<Vec<i64>> {
+        let unknowns = expr.unknowns();
+        let linear = unknowns
+            .iter()
+            .rev()
+            .find_map(|unknown| expr.split(*unknown).map(|split| (*unknown, split)));
+
+        let mut candidates = Vec::new();
+        match linear {
+            Some((unknown, (quotient, remainder))) => {
+                let others: Vec<usize> = unknowns
+                    .iter()
+                    .filter(|index| **index != unknown)
+                    .cloned()
+                    .collect();
+                let range = self.symbols[unknown].range.clone();
+                self.assignments(&others, |values| {
+                    let (quotient, remainder) =
+                        match (quotient.evaluate(values), remainder.evaluate(values)) {
+                            (Some(quotient), Some(remainder)) => (quotient, remainder),
+                            _ => return,
+                        };
+                    // With a zero quotient every value in the range solves
+                    // the expression, and the concrete run picks between them.
+                    let solutions = match target.checked_sub(remainder) {
+                        Some(rest) if quotient == 0 && rest == 0 => range.clone().collect(),
+                        Some(rest) if quotient != 0 && rest % quotient == 0 => {
+                            vec![rest / quotient]
+                        }
+                        _ => return,
+                    };
+                    for solution in solutions {
+                        if range.contains(&solution) {
+                            let mut candidate = values.clone();
+                            candidate[unknown] = solution;
+                            candidates.push(candidate);
+                        }
+                    }
+                });
+            }
+            None => self.assignments(&unknowns, |values| {
+                if expr.evaluate(values) == Some(target) {
+                    candidates.push(values.clone());
+                }
+            }),
+        }
+
+        candidates.sort();
+        candidates
+    }
+
+    /// Finds values for the symbols that leave `target` at `address`, the
+    /// first in lexicographic order. Solves the symbolic expression when the
+    /// program allows it and searches otherwise; either way the answer is
+    /// confirmed on the concrete machine.
+    pub fn solve(&self, address: usize, target: i64) -> Option<Vec<i64>> {
+        match self.evaluate(address) {
+            Ok(expr) => self
+                .candidates(&expr, target)
+                .into_iter()
+                .find(|values| self.check(values, address, target)),
+            Err(_) => self.search(address, target),
+        }
+    }
+}
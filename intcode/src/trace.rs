@@ -0,0 +1,171 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::machine::MemoryWrite;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Operand {
+    pub address: usize,
+    pub value: i64,
+}
+
+/// One executed instruction. `operands` holds the resolved address of every
+/// parameter and the value stored there before the instruction ran. `fault`
+/// describes the error the instruction stopped the machine with, if it
+/// faulted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    pub step: u64,
+    pub ip: usize,
+    pub opcode: i64,
+    pub operands: Vec<Operand>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write: Option<MemoryWrite>,
+    pub relative_base: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fault: Option<String>,
+}
+
+/// Writes executed instructions as JSON Lines.
+pub struct Tracer {
+    writer: Box<dyn Write + Send>,
+    step: u64,
+    error: Option<io::Error>,
+}
+
+impl Tracer {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Tracer {
+        Tracer {
+            writer: Box::new(writer),
+            step: 0,
+            error: None,
+        }
+    }
+
+    pub fn create(filepath: &str) -> io::Result<Tracer> {
+        Ok(Tracer::new(BufWriter::new(File::create(filepath)?)))
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        ip: usize,
+        opcode: i64,
+        operands: Vec<Operand>,
+        write: Option<MemoryWrite>,
+        relative_base: i64,
+        fault: Option<String>,
+    ) {
+        if self.error.is_some() {
+            return;
+        }
+        let record = TraceRecord {
+            step: self.step,
+            ip,
+            opcode,
+            operands,
+            write,
+            relative_base,
+            fault,
+        };
+        self.step += 1;
+
+        let result = serde_json::to_writer(&mut self.writer, &record)
+            .map_err(io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"));
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+    }
+
+    /// Flushes the trace, reporting the first write error if tracing stopped early.
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.writer.flush()
+    }
+}
+
+pub fn read_trace(filepath: &str) -> io::Result<Vec<TraceRecord>> {
+    let reader = BufReader::new(File::open(filepath)?);
+    let mut records: Vec<TraceRecord> = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str(&line).map_err(io::Error::from)?);
+    }
+
+    Ok(records)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub ip: Option<usize>,
+    pub op: Option<i64>,
+    pub address: Option<usize>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+impl Filter {
+    pub fn matches(&self, record: &TraceRecord) -> bool {
+        if self.ip.is_some_and(|ip| ip != record.ip) {
+            return false;
+        }
+        if self.op.is_some_and(|op| op != record.opcode % 100) {
+            return false;
+        }
+        if self.from.is_some_and(|from| record.step < from) {
+            return false;
+        }
+        if self.to.is_some_and(|to| record.step > to) {
+            return false;
+        }
+        match self.address {
+            Some(address) => {
+                record
+                    .operands
+                    .iter()
+                    .any(|operand| operand.address == address)
+                    || record.write.is_some_and(|write| write.address == address)
+            }
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    Record(TraceRecord, TraceRecord),
+    Ended { step: u64, longer: TraceRecord },
+}
+
+fn same_execution(lhs: &TraceRecord, rhs: &TraceRecord) -> bool {
+    lhs.ip == rhs.ip
+        && lhs.opcode == rhs.opcode
+        && lhs.operands == rhs.operands
+        && lhs.write == rhs.write
+        && lhs.relative_base == rhs.relative_base
+}
+
+/// Finds the first instruction where two traces of the same program differ.
+pub fn find_divergence(lhs: &[TraceRecord], rhs: &[TraceRecord]) -> Option<Divergence> {
+    for (left, right) in lhs.iter().zip(rhs.iter()) {
+        if !same_execution(left, right) {
+            return Some(Divergence::Record(left.clone(), right.clone()));
+        }
+    }
+
+    let common = lhs.len().min(rhs.len());
+    let longer = if lhs.len() > rhs.len() { lhs } else { rhs };
+    longer.get(common).map(|record| Divergence::Ended {
+        step: common as u64,
+        longer: record.clone(),
+    })
+}
@@ -0,0 +1,85 @@
+//! Word types the machine can compute with.
+//!
+//! `i64` faults with `ArithmeticOverflow` when `add` or `multiply` overflows,
+//! `Wrapping<i64>` wraps around and `BigInt` never overflows. Addresses,
+//! opcodes and relative base adjustments still have to fit in an `i64`.
+
+use std::fmt;
+use std::num::Wrapping;
+
+pub use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+pub trait Word:
+    Clone + Default + PartialEq + PartialOrd + fmt::Debug + fmt::Display + Send + 'static
+{
+    fn from_i64(value: i64) -> Self;
+
+    /// `None` if the value does not fit in an `i64`.
+    fn to_i64(&self) -> Option<i64>;
+
+    /// `None` if the sum cannot be represented, which faults the machine.
+    fn add(&self, other: &Self) -> Option<Self>;
+
+    /// `None` if the product cannot be represented, which faults the machine.
+    fn multiply(&self, other: &Self) -> Option<Self>;
+}
+
+impl Word for i64 {
+    fn from_i64(value: i64) -> i64 {
+        value
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        Some(*self)
+    }
+
+    fn add(&self, other: &i64) -> Option<i64> {
+        self.checked_add(*other)
+    }
+
+    fn multiply(&self, other: &i64) -> Option<i64> {
+        self.checked_mul(*other)
+    }
+}
+
+impl Word for Wrapping<i64> {
+    fn from_i64(value: i64) -> Wrapping<i64> {
+        Wrapping(value)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        Some(self.0)
+    }
+
+    fn add(&self, other: &Wrapping<i64>) -> Option<Wrapping<i64>> {
+        Some(*self + *other)
+    }
+
+    fn multiply(&self, other: &Wrapping<i64>) -> Option<Wrapping<i64>> {
+        Some(*self * *other)
+    }
+}
+
+impl Word for BigInt {
+    fn from_i64(value: i64) -> BigInt {
+        BigInt::from(value)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        ToPrimitive::to_i64(self)
+    }
+
+    fn add(&self, other: &BigInt) -> Option<BigInt> {
+        Some(self + other)
+    }
+
+    fn multiply(&self, other: &BigInt) -> Option<BigInt> {
+        Some(self * other)
+    }
+}
+
+/// Converts a parsed program to another word type.
+pub fn convert<W: Word>(program: &[i64]) -> Vec<W> {
+    program.iter().map(|value| W::from_i64(*value)).collect()
+}
@@ -0,0 +1,142 @@
+use intcode::amplifier::{amplify, optimize, permutations, AmplifierError};
+use intcode::network::NetworkState;
+use intcode::MachineError;
+
+mod common;
+
+use common::day_input;
+
+fn check_example(source: &str, phases: Vec<i64>, signal: i64, feedback: bool) {
+    let program = intcode::parse_program(source);
+    let phase_set: Vec<i64> = if feedback {
+        (5..10).collect()
+    } else {
+        (0..5).collect()
+    };
+
+    assert_eq!(amplify(&program, &phases, feedback), Ok(signal));
+    assert_eq!(
+        optimize(&program, &phase_set, 5, feedback),
+        Ok(Some((phases, signal)))
+    );
+}
+
+#[test]
+fn permutations_include_the_initial_order() {
+    let all = permutations(&[0, 1, 2], 3);
+    assert_eq!(
+        all,
+        vec![
+            vec![0, 1, 2],
+            vec![0, 2, 1],
+            vec![1, 0, 2],
+            vec![1, 2, 0],
+            vec![2, 0, 1],
+            vec![2, 1, 0],
+        ]
+    );
+
+    assert_eq!(permutations(&[5, 6, 7, 8, 9], 5).len(), 120);
+    assert_eq!(permutations(&[1, 2, 3], 2).len(), 6);
+    assert_eq!(permutations(&[1, 2], 0), vec![Vec::<i64>::new()]);
+    assert!(permutations(&[1, 2], 3).is_empty());
+}
+
+#[test]
+fn readme_series_examples() {
+    check_example(
+        "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
+        vec![4, 3, 2, 1, 0],
+        43210,
+        false,
+    );
+    check_example(
+        "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
+        vec![0, 1, 2, 3, 4],
+        54321,
+        false,
+    );
+    check_example(
+        "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,\
+         1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0",
+        vec![1, 0, 4, 3, 2],
+        65210,
+        false,
+    );
+}
+
+#[test]
+fn readme_feedback_examples() {
+    check_example(
+        "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+        vec![9, 8, 7, 6, 5],
+        139629729,
+        true,
+    );
+    check_example(
+        "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,\
+         1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,\
+         0,0,0,0,10",
+        vec![9, 7, 8, 5, 6],
+        18216,
+        true,
+    );
+}
+
+#[test]
+fn shorter_chains_and_other_phase_sets() {
+    let program = intcode::parse_program("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0");
+
+    assert_eq!(
+        optimize(&program, &[7, 1, 4], 2, false),
+        Ok(Some((vec![7, 4], 74)))
+    );
+    assert_eq!(optimize(&program, &[1, 2], 3, false), Ok(None));
+}
+
+#[test]
+fn failing_chains_are_reported() {
+    let program = intcode::parse_program("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0");
+    assert_eq!(
+        amplify(&program, &[], false),
+        Err(AmplifierError::NoAmplifiers)
+    );
+    assert_eq!(
+        optimize(&program, &[1, 2], 0, true),
+        Err(AmplifierError::NoAmplifiers)
+    );
+
+    // in [0], in [0], hlt
+    assert_eq!(
+        amplify(&[3, 0, 3, 0, 99], &[1], false),
+        Err(AmplifierError::NoSignal)
+    );
+    assert_eq!(
+        optimize(&[3, 0, 3, 0, 42], &[1, 2], 2, false),
+        Err(AmplifierError::Faulted {
+            amplifier: 0,
+            error: MachineError::UnknownOperation { ip: 4, value: 42 }
+        })
+    );
+    // in [0], in [0], in [0], hlt
+    assert_eq!(
+        amplify(&[3, 0, 3, 0, 3, 0, 99], &[1], false),
+        Err(AmplifierError::Stopped {
+            state: NetworkState::Deadlock { waiting: vec![0] }
+        })
+    );
+}
+
+#[test]
+fn day7_puzzle_input() {
+    let program = day_input(7);
+
+    assert_eq!(
+        optimize(&program, &[0, 1, 2, 3, 4], 5, false).map(|best| best.map(|(_, signal)| signal)),
+        Ok(Some(24625))
+    );
+    assert_eq!(
+        optimize(&program, &[5, 6, 7, 8, 9], 5, true).map(|best| best.map(|(_, signal)| signal)),
+        Ok(Some(36497698))
+    );
+}
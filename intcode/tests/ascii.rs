@@ -0,0 +1,62 @@
+use intcode::ascii::{self, Ascii};
+use intcode::assembler::assemble;
+use intcode::{Machine, RunState};
+
+// Echoes input until a newline, then reports how many lines it echoed as a
+// raw value and waits for more.
+const ECHO: &str = "
+    loop:
+        in   [char]
+        out  [char]
+        eq   [char], #10, [newline]
+        jz   [newline], #loop
+        add  [count], #1000, [count]
+        out  [count]
+        eq   [count], #2000, [newline]
+        jz   [newline], #loop
+        hlt
+    char:    db 0
+    newline: db 0
+    count:   db 0
+";
+
+#[test]
+fn decode_passes_non_ascii_through() {
+    assert_eq!(
+        ascii::decode(&[72, 105, 10, 1000, -1, 33]),
+        vec![
+            Ascii::Text("Hi\n".to_string()),
+            Ascii::Value(1000),
+            Ascii::Value(-1),
+            Ascii::Text("!".to_string()),
+        ]
+    );
+    assert_eq!(ascii::encode("Hi\n"), vec![72, 105, 10]);
+    assert_eq!(ascii::format(&ascii::decode(&[79, 75, 10, 420])), "OK\n420");
+}
+
+#[test]
+fn read_line_runs_until_newline() {
+    let mut machine = Machine::new(assemble(ECHO).unwrap());
+    machine.push_ascii("hello\nworld\n");
+
+    assert_eq!(
+        machine.read_line(),
+        Ok(vec![Ascii::Text("hello".to_string())])
+    );
+    assert_eq!(
+        machine.read_line(),
+        Ok(vec![Ascii::Value(1000), Ascii::Text("world".to_string())])
+    );
+    assert_eq!(machine.read_line(), Err(RunState::Halted));
+    assert_eq!(machine.pop_ascii(), vec![Ascii::Value(2000)]);
+}
+
+#[test]
+fn partial_line_stays_queued() {
+    let mut machine = Machine::new(assemble(ECHO).unwrap());
+    machine.push_ascii("abc");
+
+    assert_eq!(machine.read_line(), Err(RunState::NeedsInput));
+    assert_eq!(machine.pop_ascii(), vec![Ascii::Text("abc".to_string())]);
+}
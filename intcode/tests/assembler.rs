@@ -0,0 +1,94 @@
+use intcode::assembler::assemble;
+use intcode::disassembler::disassemble;
+use intcode::{Machine, RunState};
+
+mod common;
+
+use common::day_input;
+
+const DAYS: [u32; 7] = [2, 5, 7, 9, 11, 13, 15];
+
+fn run(program: Vec<i64>, inputs: &[i64]) -> Vec<i64> {
+    let mut machine = Machine::new(program);
+    for input in inputs {
+        machine.push_input(*input);
+    }
+    assert_eq!(machine.execute_program(), RunState::Halted);
+
+    let mut outputs = Vec::new();
+    while let Some(output) = machine.pop_output() {
+        outputs.push(output);
+    }
+    outputs
+}
+
+#[test]
+fn disassembly_of_day_inputs_assembles_back() {
+    for day in DAYS.iter() {
+        let program = day_input(*day);
+        let listing: Vec<String> = disassemble(&program)
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+
+        assert_eq!(assemble(&listing.join("\n")), Ok(program), "day{}", day);
+    }
+}
+
+#[test]
+fn labels_and_data() {
+    let source = "
+        in   [counter]
+    loop:
+        out  [counter]
+        add  [counter], #-1, [counter]
+        jnz  [counter], #loop
+        hlt
+    counter: db 0
+    ";
+
+    assert_eq!(run(assemble(source).unwrap(), &[3]), vec![3, 2, 1]);
+}
+
+#[test]
+fn relative_base_helpers() {
+    let source = "
+        arb  #stack
+        push #20
+        push #22
+        call #add_top
+        pop  [result]
+        out  [result]
+        hlt
+
+    ; replaces the two values on top of the stack with their sum
+    add_top:
+        pop  [return]
+        pop  [lhs]
+        pop  [rhs]
+        add  [lhs], [rhs], rb+0
+        arb  #1
+        push [return]
+        ret
+
+    result: db 0
+    return: db 0
+    lhs:    db 0
+    rhs:    db 0
+    stack:  dz 8
+    ";
+
+    assert_eq!(run(assemble(source).unwrap(), &[]), vec![42]);
+}
+
+#[test]
+fn errors_report_line() {
+    let error = assemble("add #1, #2, [0]\nadd #1, #2, #3").unwrap_err();
+    assert_eq!(error.line, 2);
+
+    let error = assemble("jz #0, #missing").unwrap_err();
+    assert_eq!(error.line, 1);
+
+    let error = assemble("in [0]\n5: hlt").unwrap_err();
+    assert_eq!(error.line, 2);
+}
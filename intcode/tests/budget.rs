@@ -0,0 +1,79 @@
+use intcode::{Machine, RunState};
+
+// Counts down from 5, outputting each value: out [20], add [20] -1 [20], jnz [20] #0.
+const COUNTDOWN: [i64; 21] = [
+    4, 20, 1001, 20, -1, 20, 1005, 20, 0, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5,
+];
+
+#[test]
+fn cycles_count_executed_instructions() {
+    let mut machine = Machine::new(COUNTDOWN.to_vec());
+
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(machine.cycles(), 15);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(machine.cycles(), 15);
+}
+
+#[test]
+fn exhausted_budget_resumes_where_it_stopped() {
+    let mut machine = Machine::new(COUNTDOWN.to_vec());
+    machine.set_budget(Some(4));
+
+    assert_eq!(machine.execute_program(), RunState::BudgetExhausted);
+    assert_eq!(machine.cycles(), 4);
+    assert_eq!(machine.budget(), Some(0));
+    assert_eq!(machine.ip(), 2);
+    assert_eq!(machine.execute_program(), RunState::BudgetExhausted);
+    assert_eq!(machine.cycles(), 4);
+
+    machine.set_budget(None);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+
+    let outputs: Vec<i64> = machine.output().iter().cloned().collect();
+    assert_eq!(outputs, vec![5, 4, 3, 2, 1]);
+}
+
+#[test]
+fn budget_stops_infinite_loops() {
+    let mut machine = Machine::new(vec![1105, 1, 0]);
+    machine.set_budget(Some(1000));
+
+    assert_eq!(machine.run_until_output(), RunState::BudgetExhausted);
+    assert_eq!(machine.cycles(), 1000);
+}
+
+#[test]
+fn halted_machines_report_halted_with_no_budget_left() {
+    let mut machine = Machine::new(COUNTDOWN.to_vec());
+    assert_eq!(machine.execute_program(), RunState::Halted);
+
+    machine.set_budget(Some(0));
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(machine.execute_instruction(), Some(RunState::Halted));
+}
+
+#[test]
+fn waiting_for_input_does_not_use_budget() {
+    let mut machine = Machine::new(vec![3, 0, 4, 0, 99]);
+    machine.set_budget(Some(3));
+
+    assert_eq!(machine.execute_program(), RunState::NeedsInput);
+    assert_eq!(machine.budget(), Some(3));
+    machine.push_input(7);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(machine.pop_output(), Some(7));
+    assert_eq!(machine.budget(), Some(1));
+    assert_eq!(machine.cycles(), 2);
+}
+
+#[test]
+fn snapshot_keeps_cycle_count() {
+    let mut machine = Machine::new(COUNTDOWN.to_vec());
+    machine.set_budget(Some(6));
+    machine.execute_program();
+
+    let restored = Machine::from_snapshot(machine.snapshot());
+    assert_eq!(restored.cycles(), 6);
+    assert_eq!(restored.budget(), None);
+}
@@ -0,0 +1,165 @@
+use intcode::assembler::assemble;
+use intcode::cfg::{Cfg, CodeWrite, Edge, EdgeKind, Exit, Subroutine};
+use intcode::{Machine, RunState};
+
+fn analyze(source: &str) -> Cfg {
+    Cfg::analyze(&assemble(source).unwrap())
+}
+
+fn edge(from: usize, to: usize, kind: EdgeKind) -> Edge {
+    Edge { from, to, kind }
+}
+
+const CALL: &str = "
+        arb #stack
+        call #double
+        out [value]
+        hlt
+double: arb #2
+        mul [value], #2, [value]
+        arb #-2
+        ret
+value:  db 21
+stack:  dz 8
+";
+
+#[test]
+fn branches_split_blocks() {
+    let cfg = analyze(
+        "
+        in [x]
+        jz [x], #skip
+        out #1
+skip:   out #2
+        hlt
+x:      db 0
+",
+    );
+
+    let starts: Vec<usize> = cfg.blocks.iter().map(|block| block.start).collect();
+    assert_eq!(starts, vec![0, 5, 7]);
+    assert_eq!(cfg.blocks[0].exit, Exit::Branch);
+    assert_eq!(cfg.blocks[1].exit, Exit::Fallthrough);
+    assert_eq!(cfg.blocks[2].exit, Exit::Halt);
+    assert_eq!(
+        cfg.edges,
+        vec![
+            edge(0, 5, EdgeKind::Fallthrough),
+            edge(0, 7, EdgeKind::Jump),
+            edge(5, 7, EdgeKind::Fallthrough),
+        ]
+    );
+    assert!(cfg.blocks.iter().all(|block| block.reachable));
+}
+
+#[test]
+fn code_jumped_over_is_unreachable() {
+    let cfg = analyze(
+        "
+        jnz #1, #main
+dead:   out #3
+        hlt
+main:   out #1
+        hlt
+",
+    );
+
+    assert_eq!(cfg.block_at(3).unwrap().start, 3);
+    assert!(!cfg.block_at(3).unwrap().reachable);
+    assert!(cfg.block_at(6).unwrap().reachable);
+}
+
+#[test]
+fn calls_and_returns_through_the_relative_base() {
+    let program = assemble(CALL).unwrap();
+    let mut machine = Machine::new(program.clone());
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(machine.pop_output(), Some(42));
+
+    let cfg = Cfg::analyze(&program);
+    assert_eq!(cfg.block_at(0).unwrap().exit, Exit::Call);
+    assert_eq!(cfg.block_at(14).unwrap().exit, Exit::Return);
+    assert!(cfg.edges.contains(&edge(0, 14, EdgeKind::Call)));
+    assert!(cfg.edges.contains(&edge(0, 11, EdgeKind::Return)));
+    assert_eq!(
+        cfg.subroutines,
+        vec![Subroutine {
+            entry: 14,
+            frame: Some(2),
+            callers: vec![8],
+            returns: vec![24],
+        }]
+    );
+    assert!(cfg.blocks.iter().all(|block| block.reachable));
+}
+
+#[test]
+fn writes_into_code_are_flagged() {
+    let cfg = analyze(
+        "
+        add #7, #0, [print+1]
+print:  out #0
+        hlt
+",
+    );
+
+    assert_eq!(
+        cfg.writes,
+        vec![CodeWrite {
+            writer: 0,
+            address: 5,
+            instruction: 4,
+        }]
+    );
+    assert!(cfg.block_at(4).unwrap().self_modified);
+}
+
+#[test]
+fn writes_into_the_cell_a_block_runs_into_are_flagged() {
+    let cfg = analyze(
+        "
+        in [next]
+next:   db 0
+",
+    );
+
+    assert_eq!(cfg.blocks[0].exit, Exit::IntoData);
+    assert!(cfg.blocks[0].self_modified);
+    assert_eq!(cfg.writes[0].instruction, 2);
+}
+
+#[test]
+fn dot_output() {
+    let dot = analyze(CALL).to_dot();
+
+    assert!(dot.starts_with("digraph intcode {"));
+    assert!(dot.contains("b0 -> b14 [style=bold, label=\"call\"];"));
+    assert!(dot.contains("b0 -> b11 [style=dotted, label=\"returns\"];"));
+    assert!(dot.contains("b14 [label=\"sub_14 (frame 2)\\l14: arb  #2\\l"));
+    assert!(dot.trim_end().ends_with('}'));
+}
+
+#[test]
+fn day13_structure() {
+    let program = intcode::tokenize(&format!(
+        "{}/../day13/input/input",
+        env!("CARGO_MANIFEST_DIR")
+    ));
+    let cfg = Cfg::analyze(&program);
+
+    let entries: Vec<usize> = cfg
+        .subroutines
+        .iter()
+        .map(|subroutine| subroutine.entry)
+        .collect();
+    assert_eq!(entries, vec![393, 456, 549, 578, 601]);
+    assert!(cfg
+        .subroutines
+        .iter()
+        .all(|subroutine| !subroutine.returns.is_empty()));
+    assert!(cfg.blocks.iter().all(|block| block.reachable));
+
+    let modified: Vec<usize> = cfg.writes.iter().map(|write| write.instruction).collect();
+    assert!(modified.contains(&563));
+    assert!(modified.contains(&592));
+}
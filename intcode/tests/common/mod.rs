@@ -0,0 +1,165 @@
+//! Fixtures shared by the integration tests, the benchmarks and the compiled
+//! crate's tests. Each includes this module, and none uses all of it.
+#![allow(dead_code)]
+
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+
+use intcode::{Machine, RunState, Word};
+
+/// Counts down from the input, writing each value to `x` and outputting it.
+pub const COUNTDOWN_SOURCE: &str = "
+        in [x]
+loop:   out [x]
+        add [x], #-1, [x]
+        jnz [x], #loop
+        hlt
+x:      db 0
+";
+
+/// The puzzle input of `day`, found in the repository holding this crate.
+pub fn day_input(day: u32) -> Vec<i64> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .ancestors()
+        .find(|dir| dir.join("day2").is_dir())
+        .expect("No day directories above the crate");
+    intcode::tokenize(
+        root.join(format!("day{}/input/input", day))
+            .to_str()
+            .unwrap(),
+    )
+}
+
+pub fn to_i64<W: Word>(value: W) -> i64 {
+    value.to_i64().unwrap()
+}
+
+pub fn to_run_state<W: Word>(state: RunState<W>) -> RunState {
+    match state {
+        RunState::NeedsInput => RunState::NeedsInput,
+        RunState::Output(value) => RunState::Output(to_i64(value)),
+        RunState::Halted => RunState::Halted,
+        RunState::Faulted(error) => RunState::Faulted(error),
+        RunState::BudgetExhausted => RunState::BudgetExhausted,
+    }
+}
+
+/// Something that runs an Intcode program with `i64` I/O.
+pub trait Runner {
+    fn push_input(&mut self, input: i64);
+    fn run(&mut self) -> RunState;
+    fn pop_output(&mut self) -> Option<i64>;
+    fn read(&self, address: usize) -> i64;
+}
+
+impl<W: Word> Runner for Machine<W> {
+    fn push_input(&mut self, input: i64) {
+        Machine::push_input(self, W::from_i64(input))
+    }
+
+    fn run(&mut self) -> RunState {
+        to_run_state(self.execute_program())
+    }
+
+    fn pop_output(&mut self) -> Option<i64> {
+        Machine::pop_output(self).map(to_i64)
+    }
+
+    fn read(&self, address: usize) -> i64 {
+        to_i64(self.read_memory(address))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Transcript {
+    pub outputs: Vec<i64>,
+    pub state: RunState,
+    pub first_cell: i64,
+}
+
+/// Runs until the machine stops for good, asking `respond` for the next input
+/// whenever it blocks. `None` from `respond` ends the run.
+pub fn drive<R: Runner, F: FnMut(&[i64]) -> Option<i64>>(
+    mut runner: R,
+    mut respond: F,
+) -> Transcript {
+    let mut outputs = Vec::new();
+
+    loop {
+        let state = runner.run();
+        while let Some(output) = runner.pop_output() {
+            outputs.push(output);
+        }
+
+        let input = match state {
+            RunState::NeedsInput => respond(&outputs),
+            _ => None,
+        };
+        match input {
+            Some(input) => runner.push_input(input),
+            None => {
+                return Transcript {
+                    outputs,
+                    state,
+                    first_cell: runner.read(0),
+                }
+            }
+        }
+    }
+}
+
+/// Answers with `inputs` in order, then ends the run.
+pub fn scripted(inputs: &[i64]) -> impl FnMut(&[i64]) -> Option<i64> {
+    let mut inputs: VecDeque<i64> = inputs.iter().copied().collect();
+    move |_: &[i64]| inputs.pop_front()
+}
+
+/// Paints the hull, answering each camera read with the current panel colour.
+pub fn robot(start: i64) -> impl FnMut(&[i64]) -> Option<i64> {
+    let mut panels: HashMap<(i64, i64), i64> = HashMap::new();
+    let (mut position, mut direction, mut seen) = ((0, 0), (0, -1), 0);
+    panels.insert(position, start);
+
+    move |outputs: &[i64]| {
+        for pair in outputs[seen..].chunks(2) {
+            panels.insert(position, pair[0]);
+            direction = match pair[1] {
+                0 => (direction.1, -direction.0),
+                _ => (-direction.1, direction.0),
+            };
+            position = (position.0 + direction.0, position.1 + direction.1);
+        }
+        seen = outputs.len();
+        Some(*panels.get(&position).unwrap_or(&0))
+    }
+}
+
+/// Moves the paddle towards the ball.
+pub fn joystick(outputs: &[i64]) -> Option<i64> {
+    let (mut ball, mut paddle) = (0, 0);
+    for tile in outputs.chunks(3) {
+        match tile[2] {
+            3 if tile[0] >= 0 => paddle = tile[0],
+            4 if tile[0] >= 0 => ball = tile[0],
+            _ => {}
+        }
+    }
+    Some((ball - paddle).signum())
+}
+
+/// Drives the repair droid in a fixed pseudo-random walk of 5000 moves.
+pub fn walk() -> impl FnMut(&[i64]) -> Option<i64> {
+    let mut seed: u64 = 15;
+    let mut moves = 0;
+    move |_: &[i64]| {
+        moves += 1;
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        if moves > 5000 {
+            None
+        } else {
+            Some((seed >> 33) as i64 % 4 + 1)
+        }
+    }
+}
@@ -0,0 +1,429 @@
+//! Conformance suite for the Intcode machine: the examples from the day 2, 5
+//! and 9 puzzle descriptions, plus property tests for every opcode in every
+//! addressing mode. Each program runs on all word backends, which must agree.
+
+use std::num::Wrapping;
+
+use intcode::word::BigInt;
+use intcode::{Machine, MachineError, RunState, Word};
+use proptest::prelude::*;
+
+mod common;
+
+use common::{to_i64, to_run_state};
+
+#[derive(Debug, PartialEq)]
+struct Run {
+    state: RunState,
+    outputs: Vec<i64>,
+    memory: Vec<i64>,
+    relative_base: i64,
+}
+
+fn run_on<W: Word>(program: &[i64], inputs: &[i64]) -> Run {
+    let mut machine = Machine::<W>::from_program(program);
+    for input in inputs {
+        machine.push_input(W::from_i64(*input));
+    }
+
+    let state = to_run_state(machine.execute_program());
+    let mut outputs = Vec::new();
+    while let Some(output) = machine.pop_output() {
+        outputs.push(to_i64(output));
+    }
+
+    Run {
+        state,
+        outputs,
+        memory: (0..program.len())
+            .map(|address| to_i64(machine.read_memory(address)))
+            .collect(),
+        relative_base: machine.relative_base(),
+    }
+}
+
+fn run(program: &[i64], inputs: &[i64]) -> Run {
+    let checked = run_on::<i64>(program, inputs);
+
+    assert_eq!(run_on::<Wrapping<i64>>(program, inputs), checked);
+    assert_eq!(run_on::<BigInt>(program, inputs), checked);
+    checked
+}
+
+fn final_memory(program: &[i64]) -> Vec<i64> {
+    let run = run(program, &[]);
+
+    assert_eq!(run.state, RunState::Halted);
+    run.memory
+}
+
+fn outputs(program: &[i64], inputs: &[i64]) -> Vec<i64> {
+    let run = run(program, inputs);
+
+    assert_eq!(run.state, RunState::Halted);
+    run.outputs
+}
+
+#[test]
+fn day2_examples() {
+    assert_eq!(
+        final_memory(&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]),
+        vec![3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50]
+    );
+    assert_eq!(final_memory(&[1, 0, 0, 0, 99]), vec![2, 0, 0, 0, 99]);
+    assert_eq!(final_memory(&[2, 3, 0, 3, 99]), vec![2, 3, 0, 6, 99]);
+    assert_eq!(
+        final_memory(&[2, 4, 4, 5, 99, 0]),
+        vec![2, 4, 4, 5, 99, 9801]
+    );
+    assert_eq!(
+        final_memory(&[1, 1, 1, 4, 99, 5, 6, 0, 99]),
+        vec![30, 1, 1, 4, 2, 5, 6, 0, 99]
+    );
+}
+
+#[test]
+fn day5_io_and_modes() {
+    for input in [-7, 0, 1234].iter() {
+        assert_eq!(outputs(&[3, 0, 4, 0, 99], &[*input]), vec![*input]);
+    }
+    assert_eq!(final_memory(&[1002, 4, 3, 4, 33]), vec![1002, 4, 3, 4, 99]);
+    assert_eq!(
+        final_memory(&[1101, 100, -1, 4, 0]),
+        vec![1101, 100, -1, 4, 99]
+    );
+}
+
+#[test]
+fn day5_comparisons() {
+    let equal_position = [3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+    let less_position = [3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+    let equal_immediate = [3, 3, 1108, -1, 8, 3, 4, 3, 99];
+    let less_immediate = [3, 3, 1107, -1, 8, 3, 4, 3, 99];
+
+    for input in [-8, 7, 8, 9].iter() {
+        let equal = (*input == 8) as i64;
+        let less = (*input < 8) as i64;
+
+        assert_eq!(outputs(&equal_position, &[*input]), vec![equal]);
+        assert_eq!(outputs(&less_position, &[*input]), vec![less]);
+        assert_eq!(outputs(&equal_immediate, &[*input]), vec![equal]);
+        assert_eq!(outputs(&less_immediate, &[*input]), vec![less]);
+    }
+}
+
+#[test]
+fn day5_jumps() {
+    let position = [3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+    let immediate = [3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+
+    for input in [0, 1, -5].iter() {
+        let nonzero = (*input != 0) as i64;
+
+        assert_eq!(outputs(&position, &[*input]), vec![nonzero]);
+        assert_eq!(outputs(&immediate, &[*input]), vec![nonzero]);
+    }
+}
+
+#[test]
+fn day5_compare_to_8() {
+    let program = [
+        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+        1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20,
+        1105, 1, 46, 98, 99,
+    ];
+
+    assert_eq!(outputs(&program, &[7]), vec![999]);
+    assert_eq!(outputs(&program, &[8]), vec![1000]);
+    assert_eq!(outputs(&program, &[9]), vec![1001]);
+}
+
+#[test]
+fn day9_quine() {
+    let program = [
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ];
+
+    assert_eq!(outputs(&program, &[]), program.to_vec());
+}
+
+#[test]
+fn day9_large_numbers() {
+    let product = outputs(&[1102, 34915192, 34915192, 7, 4, 7, 99, 0], &[]);
+    assert_eq!(product, vec![1219070632396864]);
+    assert_eq!(product[0].to_string().len(), 16);
+
+    assert_eq!(
+        outputs(&[104, 1125899906842624, 99], &[]),
+        vec![1125899906842624]
+    );
+}
+
+#[test]
+fn day9_relative_base() {
+    // arb #2000, arb #19, out rb-34, hlt: reads address 1985.
+    let mut program = vec![109, 2000, 109, 19, 204, -34, 99];
+    program.resize(1986, 0);
+    program[1985] = 42;
+
+    let run = run(&program, &[]);
+    assert_eq!(run.outputs, vec![42]);
+    assert_eq!(run.relative_base, 2019);
+}
+
+#[test]
+fn day9_memory_beyond_program() {
+    // add #5 [1000] [2000], out [2000], out [3000], hlt
+    let run = run(&[1001, 1000, 5, 2000, 4, 2000, 4, 3000, 99], &[]);
+
+    assert_eq!(run.state, RunState::Halted);
+    assert_eq!(run.outputs, vec![5, 0]);
+}
+
+/// Programs start with `arb #relative_base`, so the instruction under test
+/// sits at this address.
+const START: usize = 2;
+const SIZE: usize = 256;
+const LHS: usize = 200;
+const RHS: usize = 201;
+const DEST: usize = 202;
+
+fn memory(relative_base: i64) -> Vec<i64> {
+    let mut memory = vec![0; SIZE];
+    memory[0] = 109;
+    memory[1] = relative_base;
+    memory
+}
+
+fn opcode(op: i64, modes: &[i64]) -> i64 {
+    modes
+        .iter()
+        .enumerate()
+        .map(|(index, mode)| mode * 10_i64.pow(index as u32 + 2))
+        .sum::<i64>()
+        + op
+}
+
+/// The parameter that reads `value` in `mode`, storing it at `address` unless
+/// the mode is immediate.
+fn read_parameter(
+    memory: &mut [i64],
+    mode: i64,
+    value: i64,
+    address: usize,
+    relative_base: i64,
+) -> i64 {
+    if mode == 1 {
+        return value;
+    }
+    memory[address] = value;
+    write_parameter(mode, address, relative_base)
+}
+
+/// The parameter that refers to `address` in position or relative mode.
+fn write_parameter(mode: i64, address: usize, relative_base: i64) -> i64 {
+    match mode {
+        0 => address as i64,
+        _ => address as i64 - relative_base,
+    }
+}
+
+fn read_mode() -> impl Strategy<Value = i64> {
+    0..3_i64
+}
+
+fn write_mode() -> impl Strategy<Value = i64> {
+    prop_oneof![Just(0_i64), Just(2_i64)]
+}
+
+fn relative_base() -> impl Strategy<Value = i64> {
+    -100..100_i64
+}
+
+proptest! {
+    #[test]
+    fn arithmetic_and_comparisons(
+        op in prop_oneof![Just(1_i64), Just(2), Just(7), Just(8)],
+        lhs in -1_000_000_000..1_000_000_000_i64,
+        rhs in -1_000_000_000..1_000_000_000_i64,
+        modes in (read_mode(), read_mode(), write_mode()),
+        relative_base in relative_base(),
+    ) {
+        let (lhs_mode, rhs_mode, dest_mode) = modes;
+        let mut program = memory(relative_base);
+        program[START] = opcode(op, &[lhs_mode, rhs_mode, dest_mode]);
+        program[START + 1] = read_parameter(&mut program, lhs_mode, lhs, LHS, relative_base);
+        program[START + 2] = read_parameter(&mut program, rhs_mode, rhs, RHS, relative_base);
+        program[START + 3] = write_parameter(dest_mode, DEST, relative_base);
+        program[START + 4] = 99;
+
+        let mut expected = program.clone();
+        expected[DEST] = match op {
+            1 => lhs + rhs,
+            2 => lhs * rhs,
+            7 => (lhs < rhs) as i64,
+            _ => (lhs == rhs) as i64,
+        };
+
+        let run = run(&program, &[]);
+        prop_assert_eq!(run.state, RunState::Halted);
+        prop_assert_eq!(run.memory, expected);
+    }
+
+    #[test]
+    fn writes_to_immediate_parameters_fault(
+        op in prop_oneof![Just(1_i64), Just(2), Just(3), Just(7), Just(8)],
+        relative_base in relative_base(),
+    ) {
+        let mut program = memory(relative_base);
+        let size = if op == 3 { 2 } else { 4 };
+        let mut modes = vec![0; size - 1];
+        modes[size - 2] = 1;
+        program[START] = opcode(op, &modes);
+        program[START + size] = 99;
+
+        let run = run(&program, &[1]);
+        prop_assert_eq!(
+            run.state,
+            RunState::Faulted(MachineError::WriteToImmediate { ip: START })
+        );
+    }
+
+    #[test]
+    fn input(
+        value in any::<i64>(),
+        mode in write_mode(),
+        relative_base in relative_base(),
+    ) {
+        let mut program = memory(relative_base);
+        program[START] = opcode(3, &[mode]);
+        program[START + 1] = write_parameter(mode, DEST, relative_base);
+        program[START + 2] = 99;
+
+        let mut expected = program.clone();
+        expected[DEST] = value;
+
+        let run = run(&program, &[value]);
+        prop_assert_eq!(run.state, RunState::Halted);
+        prop_assert_eq!(run.memory, expected);
+    }
+
+    #[test]
+    fn input_waits_when_empty(mode in write_mode(), relative_base in relative_base()) {
+        let mut program = memory(relative_base);
+        program[START] = opcode(3, &[mode]);
+        program[START + 1] = write_parameter(mode, DEST, relative_base);
+        program[START + 2] = 99;
+
+        prop_assert_eq!(run(&program, &[]).state, RunState::NeedsInput);
+    }
+
+    #[test]
+    fn output(value in any::<i64>(), mode in read_mode(), relative_base in relative_base()) {
+        let mut program = memory(relative_base);
+        program[START] = opcode(4, &[mode]);
+        program[START + 1] = read_parameter(&mut program, mode, value, LHS, relative_base);
+        program[START + 2] = 99;
+
+        let run = run(&program, &[]);
+        prop_assert_eq!(run.state, RunState::Halted);
+        prop_assert_eq!(run.outputs, vec![value]);
+    }
+
+    #[test]
+    fn jumps(
+        op in prop_oneof![Just(5_i64), Just(6)],
+        condition in prop_oneof![Just(0_i64), any::<i64>()],
+        modes in (read_mode(), read_mode()),
+        relative_base in relative_base(),
+    ) {
+        // Falling through outputs 0; the jump target at 100 outputs 1.
+        let (condition_mode, target_mode) = modes;
+        let target = 100;
+        let mut program = memory(relative_base);
+        program[START] = opcode(op, &[condition_mode, target_mode]);
+        program[START + 1] =
+            read_parameter(&mut program, condition_mode, condition, LHS, relative_base);
+        program[START + 2] = read_parameter(&mut program, target_mode, target, RHS, relative_base);
+        program[START + 3..START + 6].copy_from_slice(&[104, 0, 99]);
+        program[target as usize..target as usize + 3].copy_from_slice(&[104, 1, 99]);
+
+        let jumped = (condition != 0) == (op == 5);
+        let run = run(&program, &[]);
+        prop_assert_eq!(run.state, RunState::Halted);
+        prop_assert_eq!(run.outputs, vec![jumped as i64]);
+    }
+
+    #[test]
+    fn adjust_relative_base(
+        adjustment in -1000..1000_i64,
+        mode in read_mode(),
+        relative_base in relative_base(),
+    ) {
+        let mut program = memory(relative_base);
+        program[START] = opcode(9, &[mode]);
+        program[START + 1] = read_parameter(&mut program, mode, adjustment, LHS, relative_base);
+        program[START + 2] = 99;
+
+        let run = run(&program, &[]);
+        prop_assert_eq!(run.state, RunState::Halted);
+        prop_assert_eq!(run.relative_base, relative_base + adjustment);
+    }
+
+    #[test]
+    fn relative_writes_follow_the_adjusted_base(
+        adjustment in -100..100_i64,
+        value in any::<i64>(),
+        relative_base in relative_base(),
+    ) {
+        // arb #adjustment, in rb+offset, hlt: the write lands at DEST.
+        let mut program = memory(relative_base);
+        program[START..START + 5].copy_from_slice(&[
+            109,
+            adjustment,
+            203,
+            write_parameter(2, DEST, relative_base + adjustment),
+            99,
+        ]);
+
+        let mut expected = program.clone();
+        expected[DEST] = value;
+
+        let run = run(&program, &[value]);
+        prop_assert_eq!(run.state, RunState::Halted);
+        prop_assert_eq!(run.memory, expected);
+    }
+
+    #[test]
+    fn unknown_modes_fault(
+        op in 1..10_i64,
+        parameter in 0..3_usize,
+        mode in 3..10_i64,
+    ) {
+        let size = intcode::decode::get_operation_size(op).unwrap();
+        prop_assume!(parameter < size - 1);
+
+        let mut program = memory(0);
+        let mut modes = vec![0; 3];
+        modes[parameter] = mode;
+        program[START] = opcode(op, &modes);
+
+        let run = run(&program, &[0]);
+        prop_assert_eq!(
+            run.state,
+            RunState::Faulted(MachineError::UnknownAddressing { ip: START, mode })
+        );
+    }
+
+    #[test]
+    fn halt_stops_immediately(relative_base in relative_base()) {
+        let mut program = memory(relative_base);
+        program[START] = 99;
+        program[START + 1] = 4;
+
+        let run = run(&program, &[]);
+        prop_assert_eq!(run.state, RunState::Halted);
+        prop_assert!(run.outputs.is_empty());
+        prop_assert_eq!(run.memory, program);
+    }
+}
@@ -0,0 +1,138 @@
+use intcode::assembler::assemble;
+use intcode::debugger::Debugger;
+use intcode::Machine;
+
+mod common;
+
+use common::COUNTDOWN_SOURCE;
+
+/// Runs `commands` against a fresh debugger on COUNTDOWN_SOURCE, returning the
+/// debugger and everything it printed.
+fn session(inputs: &[i64], commands: &[&str]) -> (Debugger, String) {
+    let mut machine = Machine::new(assemble(COUNTDOWN_SOURCE).unwrap());
+    for input in inputs {
+        machine.push_input(*input);
+    }
+    let mut debugger = Debugger::new(machine);
+    let mut output = Vec::new();
+
+    for command in commands {
+        assert!(debugger.execute_command(command, &mut output).unwrap());
+    }
+    (debugger, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn steps_and_inspects_the_machine() {
+    let (debugger, output) = session(&[2], &["step 2", "regs", "queues", "pop", "queues"]);
+
+    assert_eq!(
+        output,
+        "     0: in   [12]\n\
+         \x20    2: out  [12]\n\
+         ip 4 relative_base 0 cycles 2\n\
+         input: (empty)\n\
+         output: 2\n\
+         2\n\
+         input: (empty)\n\
+         output: (empty)\n"
+    );
+    assert_eq!(debugger.machine().ip(), 4);
+}
+
+#[test]
+fn stops_at_breakpoints_and_watchpoints() {
+    let (_, output) = session(
+        &[2],
+        &[
+            "break 8",
+            "watch 12",
+            "info",
+            "continue",
+            "continue",
+            "delete 12",
+            "delete 8",
+            "info",
+            "continue",
+        ],
+    );
+
+    assert_eq!(
+        output,
+        "breakpoints: 8\n\
+         watchpoints: 12\n\
+         watchpoint [12]: 0 -> 2 at ip 0\n\
+         watchpoint [12]: 2 -> 1 at ip 4\n\
+         breakpoints: (empty)\n\
+         watchpoints: (empty)\n\
+         halted\n"
+    );
+}
+
+#[test]
+fn dumps_memory_and_disassembles() {
+    let (_, output) = session(&[], &["mem 9 4", "dis 8 2", "push 7 8", "queues"]);
+
+    assert_eq!(
+        output,
+        "     9: 12, 2, 99, 0\n\
+         \x20      8: jnz  [12], #2\n\
+         \x20     11: hlt\n\
+         input: 7, 8\n\
+         output: (empty)\n"
+    );
+}
+
+#[test]
+fn counts_and_addresses_are_bounded() {
+    let (_, output) = session(
+        &[],
+        &[
+            "mem 18446744073709551615 2",
+            "mem 0 -1",
+            "step -3",
+            "dis 18446744073709551615 3",
+        ],
+    );
+    assert_eq!(
+        output,
+        "error: 2 cells from 18446744073709551615 run past the last address\n\
+         error: invalid count `-1`\n\
+         error: invalid count `-3`\n\
+         \x20 18446744073709551615: db   0 ; undecodable\n"
+    );
+
+    let (_, output) = session(&[], &["mem 0 9223372036854775807"]);
+    assert_eq!(output.lines().count(), 100_000 / 8);
+}
+
+#[test]
+fn reports_malformed_commands() {
+    let (_, output) = session(&[], &["", "step x", "break", "watch -1", "frob"]);
+
+    assert_eq!(
+        output,
+        "error: invalid number `x`\n\
+         error: missing address\n\
+         error: invalid address `-1`\n\
+         error: unknown command `frob`, try `help`\n"
+    );
+}
+
+#[test]
+fn runs_a_session_until_quit() {
+    let mut machine = Machine::new(assemble(COUNTDOWN_SOURCE).unwrap());
+    machine.push_input(1);
+    let mut debugger = Debugger::new(machine);
+    let mut output = Vec::new();
+
+    debugger
+        .run("step\nquit\nstep\n".as_bytes(), &mut output)
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "(icdb)      0: in   [12]\n(icdb) "
+    );
+    assert_eq!(debugger.machine().cycles(), 1);
+}
@@ -0,0 +1,48 @@
+use intcode::{Machine, RunState};
+
+mod common;
+
+use common::day_input;
+
+fn outputs(machine: &mut Machine) -> Vec<i64> {
+    let mut outputs = Vec::new();
+    while let Some(output) = machine.pop_output() {
+        outputs.push(output);
+    }
+    outputs
+}
+
+#[test]
+fn self_modifying_write_invalidates_decoded_instruction() {
+    // Loops twice over the instruction at 8. The first pass outputs [0] and
+    // then rewrites it from `out [0]` to `out #0`.
+    let program = vec![
+        1101, 0, 0, 30, // add #0, #0, [30]
+        1001, 30, 1, 30, // add [30], #1, [30]
+        4, 0, // out [0]
+        1101, 104, 0, 8, // add #104, #0, [8]
+        1008, 30, 1, 31, // eq [30], #1, [31]
+        1005, 31, 4, // jnz [31], #4
+        99,
+    ];
+    let mut machine = Machine::new(program);
+
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(outputs(&mut machine), vec![1101, 0]);
+}
+
+#[test]
+fn decode_cache_does_not_change_results() {
+    for (day, input) in [(5, 5), (9, 1), (9, 2)].iter() {
+        let mut cached = Machine::new(day_input(*day));
+        let mut uncached = Machine::new(day_input(*day));
+        uncached.set_decode_cache(false);
+        cached.push_input(*input);
+        uncached.push_input(*input);
+
+        assert_eq!(cached.execute_program(), RunState::Halted);
+        assert_eq!(uncached.execute_program(), RunState::Halted);
+        assert_eq!(outputs(&mut cached), outputs(&mut uncached));
+        assert_eq!(cached.cycles(), uncached.cycles());
+    }
+}
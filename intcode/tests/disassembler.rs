@@ -0,0 +1,130 @@
+use intcode::assembler::assemble;
+use intcode::disassembler::disassemble;
+
+fn lines(program: &[i64]) -> Vec<String> {
+    disassemble(program)
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn listing(source: &str) -> Vec<String> {
+    lines(&assemble(source).unwrap())
+}
+
+#[test]
+fn formats_every_addressing_mode() {
+    // arb #-3, add #2, #3, rb+1, out rb-1, hlt
+    assert_eq!(
+        lines(&[109, -3, 21101, 2, 3, 1, 204, -1, 99]),
+        vec![
+            "     0: arb  #-3",
+            "     2: add  #2, #3, rb+1",
+            "     6: out  rb-1",
+            "     8: hlt",
+        ]
+    );
+}
+
+#[test]
+fn readme_example_marks_self_modifying_code() {
+    assert_eq!(
+        lines(&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]),
+        vec![
+            "     0: db   1, 9, 10, 3 ; self-modifying",
+            "     4: mul  [3], [11], [0]",
+            "     8: hlt",
+            "     9: db   30, 40, 50",
+        ]
+    );
+}
+
+#[test]
+fn data_is_grouped_eight_cells_per_line() {
+    let mut program = vec![99];
+    program.extend(1..=10);
+
+    assert_eq!(
+        lines(&program),
+        vec![
+            "     0: hlt",
+            "     1: db   1, 2, 3, 4, 5, 6, 7, 8",
+            "     9: db   9, 10",
+        ]
+    );
+}
+
+#[test]
+fn jump_targets_are_followed_and_undecodable_cells_noted() {
+    // jnz #1, #4, then a halt nothing jumps to, then an unknown opcode
+    assert_eq!(
+        lines(&[1105, 1, 4, 99, 77]),
+        vec![
+            "     0: jnz  #1, #4",
+            "     3: hlt",
+            "     4: db   77 ; undecodable",
+        ]
+    );
+}
+
+#[test]
+fn sweeps_that_run_off_the_program_stay_data() {
+    // jnz #1, #5, then an add cut short by the end of the program
+    assert_eq!(
+        lines(&[1105, 1, 5, 1, 0, 99]),
+        vec!["     0: jnz  #1, #5", "     3: db   1, 0", "     5: hlt"]
+    );
+}
+
+#[test]
+fn calls_return_past_the_stack_adjustment() {
+    // `call` stores the return address, then moves the relative base before
+    // jumping. The return site only runs into known code, so it is found
+    // because the jump is recognised as a call.
+    assert_eq!(
+        listing(
+            "
+        call #sub
+        out [x]
+        jnz [x], #0
+sub:    ret
+x:      db 0
+"
+        ),
+        vec![
+            "     0: add  #9, #0, rb+0",
+            "     4: arb  #1",
+            "     6: jnz  #1, #14",
+            "     9: out  [19]",
+            "    11: jnz  [19], #0",
+            "    14: arb  #-1",
+            "    16: jz   #0, rb+0",
+            "    19: db   0",
+        ]
+    );
+}
+
+#[test]
+fn branches_between_store_and_jump_are_not_calls() {
+    assert_eq!(
+        listing(
+            "
+        add #10, #0, rb+0
+        jz [x], #sub
+        jnz #1, #sub
+        out [x]
+        jnz [x], #0
+sub:    hlt
+x:      db 0
+"
+        ),
+        vec![
+            "     0: add  #10, #0, rb+0",
+            "     4: jz   [16], #15",
+            "     7: jnz  #1, #15",
+            "    10: db   4, 16, 1005, 16, 0",
+            "    15: hlt",
+            "    16: db   0",
+        ]
+    );
+}
@@ -0,0 +1,182 @@
+//! Fuzzing harness for `Machine::execute_program`. Random programs and inputs
+//! run under a step budget and a memory limit, and every run must finish
+//! without panicking, stay within the limit and replay identically.
+//!
+//! Set `PROPTEST_CASES` for a longer session. proptest prints the minimal
+//! failing case in the corpus format, ready to be saved as a new file in
+//! `tests/corpus`, where `corpus_cases_replay` re-runs it.
+
+use std::fmt;
+use std::fs;
+
+use intcode::{Machine, RunState, Snapshot};
+use proptest::prelude::*;
+
+const BUDGET: u64 = 10_000;
+const MEMORY_LIMIT: usize = 1 << 16;
+
+/// A program and the input queued before it runs. In corpus files the first
+/// line is the program and an optional second line is the input; lines
+/// starting with `#` are comments.
+#[derive(Clone)]
+struct Case {
+    program: Vec<i64>,
+    inputs: Vec<i64>,
+}
+
+impl Case {
+    fn parse(text: &str) -> Case {
+        let mut lines = text
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        let program = intcode::parse_program(lines.next().expect("missing program"));
+        let inputs = match lines.next() {
+            Some(line) => intcode::parse_program(line),
+            None => Vec::new(),
+        };
+
+        Case { program, inputs }
+    }
+}
+
+impl fmt::Debug for Case {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format = |values: &[i64]| {
+            values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+
+        writeln!(f)?;
+        writeln!(f, "{}", format(&self.program))?;
+        if !self.inputs.is_empty() {
+            writeln!(f, "{}", format(&self.inputs))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    state: RunState,
+    snapshot: Snapshot,
+}
+
+fn limit(case: &Case) -> usize {
+    MEMORY_LIMIT.max(case.program.len())
+}
+
+fn create_machine(case: &Case) -> Machine {
+    let mut machine = Machine::new(case.program.clone());
+    machine.set_memory_limit(Some(limit(case)));
+    for input in case.inputs.iter() {
+        machine.push_input(*input);
+    }
+    machine
+}
+
+/// Runs `machine` until it stops with at most `budget` more instructions.
+fn run(case: &Case, machine: &mut Machine, budget: u64) -> RunState {
+    machine.set_budget(Some(budget));
+    let state = machine.execute_program();
+
+    assert!(machine.cycles() <= BUDGET, "ran past the budget");
+    assert!(
+        machine.allocated() <= limit(case),
+        "allocated {} cells",
+        machine.allocated()
+    );
+    state
+}
+
+fn execute(case: &Case) -> Outcome {
+    let mut machine = create_machine(case);
+    let state = run(case, &mut machine, BUDGET);
+
+    Outcome {
+        state,
+        snapshot: machine.snapshot(),
+    }
+}
+
+/// Runs half the budget, restores a snapshot and runs the rest.
+fn execute_resumed(case: &Case) -> Outcome {
+    let mut machine = create_machine(case);
+    run(case, &mut machine, BUDGET / 2);
+
+    let mut machine = Machine::from_snapshot(machine.snapshot());
+    machine.set_memory_limit(Some(limit(case)));
+    let remaining = BUDGET - machine.cycles();
+    let state = run(case, &mut machine, remaining);
+
+    Outcome {
+        state,
+        snapshot: machine.snapshot(),
+    }
+}
+
+fn check(case: &Case) {
+    let outcome = execute(case);
+
+    assert_eq!(execute(case), outcome, "replay diverged");
+    assert_eq!(execute_resumed(case), outcome, "resumed run diverged");
+}
+
+/// Mostly instruction-shaped values and small operands, with some extremes.
+fn word() -> impl Strategy<Value = i64> {
+    prop_oneof![
+        4 => (1..10_i64, 0..4_i64, 0..4_i64, 0..3_i64)
+            .prop_map(|(op, first, second, third)| op + first * 100 + second * 1000 + third * 10000),
+        1 => Just(99_i64),
+        4 => -10..300_i64,
+        1 => prop_oneof![Just(i64::MAX), Just(i64::MIN), Just(-1_i64), Just(0_i64)],
+        1 => any::<i64>(),
+    ]
+}
+
+fn case() -> impl Strategy<Value = Case> {
+    (
+        prop::collection::vec(word(), 1..64),
+        prop::collection::vec(word(), 0..8),
+    )
+        .prop_map(|(program, inputs)| Case { program, inputs })
+}
+
+proptest! {
+    #[test]
+    fn random_programs(case in case()) {
+        check(&case);
+    }
+}
+
+#[test]
+fn corpus_cases_replay() {
+    let directory = format!("{}/tests/corpus", env!("CARGO_MANIFEST_DIR"));
+    let mut paths: Vec<_> = fs::read_dir(&directory)
+        .expect("missing corpus directory")
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+
+    assert!(!paths.is_empty());
+    for path in paths {
+        let case = Case::parse(&fs::read_to_string(&path).unwrap());
+        let outcome = execute(&case);
+
+        assert_eq!(
+            execute(&case),
+            outcome,
+            "{}: replay diverged",
+            path.display()
+        );
+        assert_eq!(
+            execute_resumed(&case),
+            outcome,
+            "{}: resumed run diverged",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeSet;
+
+use intcode::assembler::assemble;
+use intcode::debugger::Debugger;
+use intcode::{Machine, RunState, Snapshot};
+
+mod common;
+
+use common::{day_input, COUNTDOWN_SOURCE};
+
+/// Cells a write allocated stay allocated when it is undone, so compare dense
+/// memory as if padded with zeros.
+fn pad(mut snapshot: Snapshot, length: usize) -> Snapshot {
+    snapshot.memory.resize(length, 0);
+    snapshot
+}
+
+#[test]
+fn stepping_back_retraces_every_state() {
+    let mut machine = Machine::new(day_input(9));
+    machine.set_history(Some(1000));
+    machine.push_input(1);
+
+    let mut snapshots = vec![machine.snapshot()];
+    while machine.execute_instruction().is_none() {
+        snapshots.push(machine.snapshot());
+    }
+    assert_eq!(machine.output().len(), 1);
+
+    snapshots.pop();
+    while let Some(snapshot) = snapshots.pop() {
+        assert!(machine.step_back());
+        let length = machine.snapshot().memory.len();
+        assert_eq!(machine.snapshot(), pad(snapshot, length));
+    }
+    assert!(!machine.step_back());
+}
+
+#[test]
+fn history_is_bounded() {
+    let mut machine = Machine::new(assemble(COUNTDOWN_SOURCE).unwrap());
+    machine.set_history(Some(10));
+    machine.push_input(20);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+
+    assert_eq!(machine.history().unwrap().len(), 10);
+    for _ in 0..10 {
+        assert!(machine.step_back());
+    }
+    assert!(!machine.step_back());
+    assert_eq!(machine.cycles(), 61 - 10);
+}
+
+#[test]
+fn outputs_are_taken_back() {
+    let mut machine = Machine::new(assemble(COUNTDOWN_SOURCE).unwrap());
+    machine.set_history(Some(100));
+    machine.push_input(2);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(machine.output().len(), 2);
+
+    while machine.step_back() {}
+    assert_eq!(machine.ip(), 0);
+    assert!(machine.output().is_empty());
+    assert_eq!(machine.input().iter().cloned().collect::<Vec<_>>(), vec![2]);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(
+        machine.output().iter().cloned().collect::<Vec<_>>(),
+        vec![2, 1]
+    );
+}
+
+#[test]
+fn run_back_stops_at_breakpoints() {
+    let mut machine = Machine::new(assemble(COUNTDOWN_SOURCE).unwrap());
+    machine.set_history(Some(100));
+    machine.push_input(3);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+
+    let breakpoints: BTreeSet<usize> = [2].iter().cloned().collect();
+    assert!(machine.run_back_to_breakpoint(&breakpoints));
+    assert_eq!(machine.ip(), 2);
+    assert_eq!(machine.read_memory(12), 1);
+    assert!(machine.run_back_to_breakpoint(&breakpoints));
+    assert_eq!(machine.read_memory(12), 2);
+    assert!(machine.run_back_to_breakpoint(&breakpoints));
+    assert_eq!(machine.read_memory(12), 3);
+    assert!(!machine.run_back_to_breakpoint(&breakpoints));
+    assert_eq!(machine.ip(), 0);
+}
+
+#[test]
+fn last_writer_finds_the_instruction() {
+    let mut machine = Machine::new(assemble(COUNTDOWN_SOURCE).unwrap());
+    machine.set_history(Some(100));
+    machine.push_input(3);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+
+    let history = machine.history().unwrap();
+    let record = history.last_writer(12).unwrap();
+    assert_eq!(record.ip, 4);
+    assert_eq!(record.write.unwrap().value, 0);
+    assert!(history.last_writer(0).is_none());
+
+    machine.set_history(Some(100));
+    assert!(machine.history().unwrap().is_empty());
+}
+
+#[test]
+fn debugger_steps_backwards() {
+    let mut machine = Machine::new(assemble(COUNTDOWN_SOURCE).unwrap());
+    machine.push_input(3);
+    let mut debugger = Debugger::new(machine);
+    let mut output = Vec::new();
+
+    for command in &["step 3", "back 2", "who 12", "break 2", "continue", "rc"] {
+        debugger.execute_command(command, &mut output).unwrap();
+    }
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.contains("[12]: 0 -> 3 at cycle 0\n     0: in   [12]\n"));
+    assert!(output.ends_with("breakpoint at ip 2\n     2: out  [12]\n"));
+    assert_eq!(debugger.machine().read_memory(12), 3);
+    assert_eq!(debugger.machine().cycles(), 1);
+}
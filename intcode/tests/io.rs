@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+use std::io::Cursor;
+use std::sync::mpsc;
+
+use intcode::amplifier::permutations;
+use intcode::io::{LineInput, LineOutput};
+use intcode::{Machine, RunState};
+
+mod common;
+
+use common::day_input;
+
+// Outputs every value it reads doubled, halting when it reads 0.
+const DOUBLER: [i64; 15] = [3, 20, 1006, 20, 14, 1002, 20, 2, 20, 4, 20, 1106, 0, 0, 99];
+
+#[test]
+fn queue_ports() {
+    let mut machine = Machine::new(DOUBLER.to_vec());
+    let mut input: VecDeque<i64> = vec![1, 2, 3, 0].into_iter().collect();
+    let mut output = Vec::new();
+
+    assert_eq!(machine.run_with(&mut input, &mut output), RunState::Halted);
+    assert_eq!(output, vec![2, 4, 6]);
+}
+
+#[test]
+fn closure_ports() {
+    let mut machine = Machine::new(DOUBLER.to_vec());
+    let mut next = 4;
+    let mut sum = 0;
+
+    let state = machine.run_with(
+        &mut || {
+            next -= 1;
+            Some(next)
+        },
+        &mut |value| sum += value,
+    );
+    assert_eq!(state, RunState::Halted);
+    assert_eq!(sum, 12);
+}
+
+#[test]
+fn queued_input_comes_first_and_run_resumes() {
+    let mut machine = Machine::new(DOUBLER.to_vec());
+    let mut input: VecDeque<i64> = vec![2].into_iter().collect();
+    let mut output = Vec::new();
+    machine.push_input(1);
+
+    assert_eq!(
+        machine.run_with(&mut input, &mut output),
+        RunState::NeedsInput
+    );
+    input.push_back(0);
+    assert_eq!(machine.run_with(&mut input, &mut output), RunState::Halted);
+    assert_eq!(output, vec![2, 4]);
+}
+
+#[test]
+fn line_ports() {
+    let mut machine = Machine::new(DOUBLER.to_vec());
+    let mut written = Vec::new();
+
+    let state = machine.run_with(
+        &mut LineInput::new(Cursor::new("1, 2\n\n3 0\n")),
+        &mut LineOutput::new(&mut written),
+    );
+    assert_eq!(state, RunState::Halted);
+    assert_eq!(String::from_utf8(written).unwrap(), "2\n4\n6\n");
+}
+
+/// Day 7 part 2 with every amplifier on its own thread, wired by channels.
+fn feedback_loop(program: &[i64], phases: &[i64]) -> i64 {
+    let (senders, receivers): (Vec<_>, Vec<_>) = phases.iter().map(|_| mpsc::channel()).unzip();
+    let (signals, last_signal) = mpsc::channel();
+    let mut handles = Vec::new();
+
+    senders[0].send(0).unwrap();
+    for (index, (phase, input)) in phases.iter().zip(receivers).enumerate() {
+        let mut amp = Machine::new(program.to_vec());
+        amp.push_input(*phase);
+
+        let next = senders[(index + 1) % phases.len()].clone();
+        handles.push(if index + 1 == phases.len() {
+            let signals = signals.clone();
+            amp.spawn(input, move |value| {
+                let _ = next.send(value);
+                let _ = signals.send(value);
+            })
+        } else {
+            amp.spawn(input, next)
+        });
+    }
+    drop(senders);
+    drop(signals);
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap().1, RunState::Halted);
+    }
+    last_signal.iter().last().unwrap()
+}
+
+#[test]
+fn threaded_amplifier_ring() {
+    let program = day_input(7);
+    let best = permutations(&[5, 6, 7, 8, 9], 5)
+        .iter()
+        .map(|phases| feedback_loop(&program, phases))
+        .max();
+    assert_eq!(best, Some(36497698));
+}
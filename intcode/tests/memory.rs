@@ -0,0 +1,174 @@
+use intcode::memory::{DenseMemory, Memory, PagedMemory};
+use intcode::{Machine, MachineError, RunState};
+
+mod common;
+
+use common::day_input;
+
+const HIGH: i64 = i64::MAX - 1;
+
+fn high_write_program() -> Vec<i64> {
+    vec![1101, 1, 2, HIGH, 4, HIGH, 99]
+}
+
+fn paged(program: Vec<i64>) -> Machine {
+    Machine::with_memory(Box::new(PagedMemory::new(program)))
+}
+
+#[test]
+fn paged_memory_writes_near_max_address() {
+    let mut machine = paged(high_write_program());
+
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(machine.pop_output(), Some(3));
+    assert_eq!(machine.read_memory(HIGH as usize), 3);
+}
+
+#[test]
+fn dense_memory_faults_instead_of_allocating_near_max_address() {
+    let mut machine = Machine::new(high_write_program());
+
+    assert_eq!(
+        machine.execute_program(),
+        RunState::Faulted(MachineError::MemoryLimitExceeded {
+            ip: 0,
+            address: HIGH as usize
+        })
+    );
+}
+
+#[test]
+fn dense_memory_limit_is_enforced() {
+    let mut machine = Machine::new(vec![1101, 1, 2, 100, 99]);
+    machine.set_memory_limit(Some(64));
+
+    assert_eq!(
+        machine.execute_program(),
+        RunState::Faulted(MachineError::MemoryLimitExceeded {
+            ip: 0,
+            address: 100
+        })
+    );
+}
+
+#[test]
+fn dense_memory_has_a_default_limit() {
+    let beyond = intcode::memory::DENSE_LIMIT as i64;
+    let mut machine = Machine::new(vec![1101, 1, 2, beyond, 99]);
+
+    assert_eq!(
+        machine.execute_program(),
+        RunState::Faulted(MachineError::MemoryLimitExceeded {
+            ip: 0,
+            address: beyond as usize
+        })
+    );
+}
+
+#[test]
+fn dense_memory_limit_can_be_removed() {
+    let mut memory = DenseMemory::new(vec![1, 2, 3]);
+    let beyond = intcode::memory::DENSE_LIMIT;
+    assert!(!memory.write(beyond, 7));
+
+    memory.set_limit(None);
+    assert!(memory.write(beyond, 7));
+    assert_eq!(memory.read(beyond), 7);
+    assert!(!memory.write(usize::MAX, 9));
+}
+
+#[test]
+fn paged_memory_extent_follows_the_highest_write() {
+    let page = intcode::memory::PAGE_SIZE;
+    let mut memory = PagedMemory::new(vec![1, 2, 3, 0, 0]);
+    assert_eq!(memory.extent(), 5);
+
+    assert!(memory.write(10 * page + 1, 7));
+    assert!(memory.write(4 * page, 5));
+    assert_eq!(memory.extent(), 10 * page + 2);
+    assert!(memory.write(20 * page, 0));
+    assert_eq!(memory.extent(), 20 * page + 1);
+    assert_eq!(memory.allocated(), 3 * page);
+
+    assert!(memory.write(usize::MAX, 9));
+    assert_eq!(memory.extent(), usize::MAX);
+}
+
+#[test]
+fn running_off_the_end_faults_on_both_backends() {
+    // add #1, #1, [3]
+    let program = vec![1101, 1, 1, 3];
+    let expected = RunState::Faulted(MachineError::IpOutOfRange { ip: 4 });
+
+    assert_eq!(Machine::new(program.clone()).execute_program(), expected);
+    assert_eq!(paged(program).execute_program(), expected);
+}
+
+#[test]
+fn paged_memory_limit_is_enforced() {
+    let mut machine = paged(high_write_program());
+    machine.set_memory_limit(Some(intcode::memory::PAGE_SIZE));
+
+    assert_eq!(
+        machine.execute_program(),
+        RunState::Faulted(MachineError::MemoryLimitExceeded {
+            ip: 0,
+            address: HIGH as usize
+        })
+    );
+}
+
+#[test]
+fn relative_address_overflow_faults() {
+    let mut machine = paged(vec![109, i64::MAX, 204, 1, 99]);
+
+    assert_eq!(
+        machine.execute_program(),
+        RunState::Faulted(MachineError::AddressOverflow { ip: 2 })
+    );
+}
+
+#[test]
+fn relative_base_overflow_faults() {
+    let mut machine = Machine::new(vec![109, i64::MAX, 109, 1, 99]);
+
+    assert_eq!(
+        machine.execute_program(),
+        RunState::Faulted(MachineError::AddressOverflow { ip: 2 })
+    );
+}
+
+#[test]
+fn negative_relative_address_faults() {
+    let mut machine = paged(vec![109, -5, 204, 1, 99]);
+
+    assert_eq!(
+        machine.execute_program(),
+        RunState::Faulted(MachineError::NegativeAddress { ip: 2, address: -4 })
+    );
+}
+
+#[test]
+fn snapshot_round_trips_paged_memory() {
+    let mut machine = paged(high_write_program());
+    machine.execute_program();
+
+    let restored = Machine::from_snapshot(machine.snapshot());
+    assert_eq!(restored.read_memory(HIGH as usize), 3);
+    assert_eq!(restored.read_memory(0), 1101);
+}
+
+#[test]
+fn day9_boost_matches_on_both_backends() {
+    for (input, expected) in [(1, 2453265701), (2, 80805)].iter() {
+        let mut dense = Machine::with_memory(Box::new(DenseMemory::new(day_input(9))));
+        let mut sparse = paged(day_input(9));
+        dense.push_input(*input);
+        sparse.push_input(*input);
+
+        assert_eq!(dense.execute_program(), RunState::Halted);
+        assert_eq!(sparse.execute_program(), RunState::Halted);
+        assert_eq!(dense.pop_output(), Some(*expected));
+        assert_eq!(sparse.pop_output(), Some(*expected));
+    }
+}
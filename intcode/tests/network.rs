@@ -0,0 +1,183 @@
+use intcode::amplifier::permutations;
+use intcode::assembler::assemble;
+use intcode::network::{Network, NetworkState, Packet, Schedule, Topology};
+use intcode::{Machine, MachineError};
+
+mod common;
+
+use common::day_input;
+
+const SCHEDULES: [Schedule; 2] = [Schedule::RoundRobin, Schedule::Threaded];
+
+// Reads a value, outputs it doubled and halts.
+const DOUBLE_ONCE: [i64; 10] = [3, 9, 1002, 9, 2, 9, 4, 9, 99, 0];
+
+// Reads its bus address. Machine 0 then sends `1, 0, 7`, and every machine
+// forwards each packet it receives to the next address.
+const FORWARDER: &str = "
+        in   [addr]
+        jnz  [addr], #listen
+        out  #1
+        out  #0
+        out  #7
+    listen:
+        in   [x]
+        eq   [x], #-1, [empty]
+        jnz  [empty], #listen
+        in   [y]
+        add  [addr], #1, [dest]
+        out  [dest]
+        out  [x]
+        out  [y]
+        jz   #0, #listen
+    addr:  db 0
+    x:     db 0
+    y:     db 0
+    dest:  db 0
+    empty: db 0
+";
+
+fn machines(program: &[i64], inputs: &[i64]) -> Vec<Machine> {
+    inputs
+        .iter()
+        .map(|input| {
+            let mut machine = Machine::new(program.to_vec());
+            machine.push_input(*input);
+            machine
+        })
+        .collect()
+}
+
+fn best_signal(phases: Vec<i64>, topology: Topology, schedule: Schedule) -> i64 {
+    let program = day_input(7);
+    permutations(&phases, phases.len())
+        .iter()
+        .map(|phases| {
+            let mut network = Network::new(machines(&program, phases), topology);
+            network.push_input(0, 0);
+
+            assert_eq!(network.run(schedule), NetworkState::Halted);
+            *network.output().last().unwrap()
+        })
+        .max()
+        .unwrap()
+}
+
+#[test]
+fn pipeline() {
+    for schedule in SCHEDULES.iter() {
+        let mut network = Network::new(machines(&DOUBLE_ONCE, &[]), Topology::Pipeline);
+        assert_eq!(network.run(*schedule), NetworkState::Halted);
+
+        let mut network = Network::new(
+            vec![Machine::new(DOUBLE_ONCE.to_vec()); 3],
+            Topology::Pipeline,
+        );
+        network.push_input(0, 5);
+        assert_eq!(network.run(*schedule), NetworkState::Halted);
+        assert_eq!(network.output(), &[40]);
+    }
+}
+
+#[test]
+fn day7_amplifiers() {
+    for schedule in SCHEDULES.iter() {
+        assert_eq!(
+            best_signal((0..5).collect(), Topology::Pipeline, *schedule),
+            24625
+        );
+        assert_eq!(
+            best_signal((5..10).collect(), Topology::Ring, *schedule),
+            36497698
+        );
+    }
+}
+
+#[test]
+fn deadlock_is_reported() {
+    for schedule in SCHEDULES.iter() {
+        let mut network = Network::new(vec![Machine::new(DOUBLE_ONCE.to_vec()); 2], Topology::Ring);
+        assert_eq!(
+            network.run(*schedule),
+            NetworkState::Deadlock {
+                waiting: vec![0, 1]
+            }
+        );
+    }
+}
+
+#[test]
+fn halted_upstream_deadlocks_downstream() {
+    for schedule in SCHEDULES.iter() {
+        let program = vec![99];
+        let mut network = Network::new(
+            vec![Machine::new(program), Machine::new(DOUBLE_ONCE.to_vec())],
+            Topology::Pipeline,
+        );
+        assert_eq!(
+            network.run(*schedule),
+            NetworkState::Deadlock { waiting: vec![1] }
+        );
+    }
+}
+
+#[test]
+fn fault_is_reported() {
+    for schedule in SCHEDULES.iter() {
+        let mut network = Network::new(
+            vec![
+                Machine::new(DOUBLE_ONCE.to_vec()),
+                Machine::new(vec![3, 0, 42]),
+            ],
+            Topology::Pipeline,
+        );
+        network.push_input(0, 21);
+        assert_eq!(
+            network.run(*schedule),
+            NetworkState::Faulted {
+                machine: 1,
+                error: MachineError::UnknownOperation { ip: 2, value: 42 }
+            }
+        );
+    }
+}
+
+#[test]
+fn bus_without_nat_goes_idle() {
+    let program = assemble(FORWARDER).unwrap();
+
+    for schedule in SCHEDULES.iter() {
+        let mut network = Network::new(machines(&program, &[0, 1, 2]), Topology::Bus { nat: None });
+        assert_eq!(network.run(*schedule), NetworkState::Idle);
+        assert_eq!(
+            network.outbound(),
+            &[Packet {
+                destination: 3,
+                x: 0,
+                y: 7
+            }]
+        );
+        assert!(network.nat_sent().is_empty());
+    }
+}
+
+#[test]
+fn bus_nat_stops_on_repeated_packet() {
+    let program = assemble(FORWARDER).unwrap();
+    let resent = Packet {
+        destination: 0,
+        x: 0,
+        y: 7,
+    };
+
+    for schedule in SCHEDULES.iter() {
+        let mut network = Network::new(
+            machines(&program, &[0, 1, 2]),
+            Topology::Bus { nat: Some(3) },
+        );
+        assert_eq!(network.run(*schedule), NetworkState::Idle);
+        assert_eq!(network.nat_sent(), &[resent, resent]);
+        assert!(network.pending(0).is_empty());
+        assert!(network.outbound().is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+use intcode::patch::{split_args, Patch, PatchError, Poke};
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn parses_patch_file_with_comments() {
+    let patch = Patch::parse("# 1202 program alarm\n\n1 = 12, 2 # noun, verb\n0=2\n").unwrap();
+
+    assert_eq!(
+        patch.pokes(),
+        &[
+            Poke {
+                address: 1,
+                values: vec![12, 2]
+            },
+            Poke {
+                address: 0,
+                values: vec![2]
+            },
+        ]
+    );
+}
+
+#[test]
+fn invalid_pokes_report_their_line() {
+    assert_eq!(
+        Patch::parse("0 = 2\n\n1 = twelve\n"),
+        Err(PatchError::InvalidPoke {
+            line: 3,
+            text: "1 = twelve".to_string()
+        })
+    );
+    assert_eq!(
+        Patch::new().add_poke("12"),
+        Err(PatchError::InvalidPoke {
+            line: 0,
+            text: "12".to_string()
+        })
+    );
+}
+
+#[test]
+fn out_of_range_poke_leaves_program_unchanged() {
+    let mut program = vec![1, 0, 0, 0, 99];
+    let mut patch = Patch::new();
+    patch.poke(1, &[4]);
+    patch.poke(3, &[5, 6, 7]);
+
+    assert_eq!(
+        patch.apply(&mut program),
+        Err(PatchError::OutOfRange {
+            address: 5,
+            length: 5
+        })
+    );
+    assert_eq!(program, vec![1, 0, 0, 0, 99]);
+}
+
+#[test]
+fn pokes_near_the_last_address_are_out_of_range() {
+    let mut patch = Patch::new();
+    patch.add_poke(&format!("{}=1,2", usize::MAX)).unwrap();
+
+    assert_eq!(
+        patch.patched(vec![0; 4]),
+        Err(PatchError::OutOfRange {
+            address: usize::MAX,
+            length: 4
+        })
+    );
+}
+
+#[test]
+fn empty_pokes_write_nothing() {
+    let mut patch = Patch::new();
+    patch.poke(usize::MAX, &[]);
+    patch.poke(4, &[]);
+
+    assert_eq!(patch.validate(4), Ok(()));
+    assert_eq!(patch.patched(vec![1, 2, 3, 4]), Ok(vec![1, 2, 3, 4]));
+}
+
+#[test]
+fn later_pokes_win_in_variants() {
+    let program = vec![1, 0, 0, 0, 99];
+    let mut patch = Patch::new();
+    patch.poke(1, &[1, 2]);
+    patch.add_poke("2=3").unwrap();
+
+    assert_eq!(patch.variant(&program).unwrap(), vec![1, 1, 3, 0, 99]);
+    assert_eq!(program, vec![1, 0, 0, 0, 99]);
+}
+
+#[test]
+fn split_args_takes_poke_and_patch_options() {
+    let filepath = std::env::temp_dir().join(format!("intcode-patch-{}", std::process::id()));
+    std::fs::write(&filepath, "2 = 7\n").unwrap();
+    let filepath = filepath.to_str().unwrap().to_string();
+
+    let (rest, patch) = split_args(args(&[
+        "day2", "--poke", "1=5", "input", "--patch", &filepath, "3",
+    ]))
+    .unwrap();
+    std::fs::remove_file(&filepath).unwrap();
+
+    assert_eq!(rest, args(&["day2", "input", "3"]));
+    assert_eq!(patch.variant(&[0, 0, 0]).unwrap(), vec![0, 5, 7]);
+
+    assert_eq!(
+        split_args(args(&["day2", "--poke"])),
+        Err(PatchError::MissingArgument {
+            option: "--poke".to_string()
+        })
+    );
+}
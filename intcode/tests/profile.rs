@@ -0,0 +1,119 @@
+use intcode::assembler::assemble;
+use intcode::profile::{Loop, Profile};
+use intcode::{Machine, RunState};
+
+fn profile(source: &str, inputs: &[i64]) -> (Vec<i64>, Profile) {
+    let program = assemble(source).unwrap();
+    let mut machine = Machine::new(program.clone());
+    machine.set_profile(Profile::new());
+    for input in inputs {
+        machine.push_input(*input);
+    }
+
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    (program, machine.take_profile().unwrap())
+}
+
+const COUNTDOWN: &str = "
+        in [x]
+loop:   out [x]
+        add [x], #-1, [x]
+        jnz [x], #loop
+        jnz #1, #done
+unused: out #0
+        hlt
+done:   hlt
+x:      db 0
+";
+
+#[test]
+fn counts_executions_and_memory_traffic() {
+    let (_, profile) = profile(COUNTDOWN, &[3]);
+
+    let executions: Vec<(usize, u64)> = profile
+        .executions()
+        .iter()
+        .map(|(ip, count)| (*ip, *count))
+        .collect();
+    assert_eq!(
+        executions,
+        vec![(0, 1), (2, 3), (4, 3), (8, 3), (11, 1), (17, 1)]
+    );
+    assert_eq!(profile.operations().get(&1), Some(&3));
+    assert_eq!(profile.operations().get(&99), Some(&1));
+    assert_eq!(profile.total(), 12);
+
+    assert_eq!(profile.reads().get(&18), Some(&9));
+    assert_eq!(profile.writes().get(&18), Some(&4));
+    assert_eq!(profile.max_address(), 18);
+}
+
+#[test]
+fn finds_hot_loops() {
+    let (_, profile) = profile(COUNTDOWN, &[5]);
+
+    assert_eq!(
+        profile.hot_loops(),
+        vec![Loop {
+            start: 2,
+            end: 8,
+            iterations: 4,
+            instructions: 15,
+        }]
+    );
+}
+
+#[test]
+fn annotates_coverage() {
+    let (program, profile) = profile(COUNTDOWN, &[3]);
+    let annotated = profile.annotate(&program);
+    let lines: Vec<&str> = annotated.lines().collect();
+
+    assert_eq!(lines[1], "         3 |     2: out  [18]");
+    assert_eq!(lines[5], "         - |    14: out  #0");
+    assert_eq!(lines[6], "         - |    16: hlt");
+    assert_eq!(lines[8], "           |    18: db   0 ; self-modifying");
+    assert_eq!(lines[9], "coverage: 6 of 8 instructions executed");
+}
+
+/// `call` raises the relative base at the call site and the callees raise it
+/// again on entry, so each call shows up as two frames.
+#[test]
+fn folds_stacks_by_relative_base() {
+    let (_, profile) = profile(
+        "
+        arb #stack
+        call #outer
+        hlt
+outer:  arb #1
+        call #inner
+        arb #-1
+        ret
+inner:  arb #1
+        out #1
+        arb #-1
+        ret
+stack:  dz 8
+",
+        &[],
+    );
+
+    assert_eq!(
+        profile.folded(),
+        "main;sub_0 4\n\
+         main;sub_0;sub_6 3\n\
+         main;sub_0;sub_6;sub_12 4\n\
+         main;sub_0;sub_6;sub_12;sub_18 3\n\
+         main;sub_0;sub_6;sub_12;sub_18;sub_30 3\n"
+    );
+}
+
+#[test]
+fn summary_lists_the_busiest_addresses() {
+    let (_, profile) = profile(COUNTDOWN, &[3]);
+    let summary = profile.summary(1);
+
+    assert!(summary.starts_with("instructions: 12\nmax address: 18\n"));
+    assert!(summary.contains("reads (1 addresses):\n  [18]          9\n"));
+    assert!(summary.ends_with("hot loops:\n  2..=8          9 instructions, 2 iterations\n"));
+}
@@ -0,0 +1,88 @@
+use intcode::memory::PagedMemory;
+use intcode::profile::Profile;
+use intcode::{Machine, RunState, Snapshot};
+
+mod common;
+
+use common::day_input;
+
+/// Saves `machine` to a file and loads it back into a new machine.
+fn save_and_load(machine: &Machine, name: &str) -> Machine {
+    let filepath =
+        std::env::temp_dir().join(format!("intcode-snapshot-{}-{}", name, std::process::id()));
+    let filepath = filepath.to_str().unwrap();
+
+    machine.snapshot().save(filepath).unwrap();
+    let snapshot = Snapshot::load(filepath).unwrap();
+    std::fs::remove_file(filepath).unwrap();
+
+    assert_eq!(snapshot, machine.snapshot());
+    Machine::from_snapshot(snapshot)
+}
+
+/// Runs `machine` to completion, returning its final state and snapshot.
+fn finish(mut machine: Machine) -> (RunState, Snapshot) {
+    let state = machine.execute_program();
+    (state, machine.snapshot())
+}
+
+#[test]
+fn resumed_runs_match_uninterrupted_runs() {
+    let mut expected = Machine::new(day_input(9));
+    expected.push_input(2);
+    let expected = finish(expected);
+
+    for budget in [0, 1, 100, 10000].iter() {
+        let mut machine = Machine::new(day_input(9));
+        machine.push_input(2);
+        machine.set_budget(Some(*budget));
+        assert_eq!(machine.execute_program(), RunState::BudgetExhausted);
+
+        let restored = save_and_load(&machine, "dense");
+        assert_eq!(restored.cycles(), *budget);
+        assert_eq!(finish(restored), expected, "resumed after {}", budget);
+    }
+}
+
+#[test]
+fn paged_memory_and_queues_survive_a_round_trip() {
+    // in [2000000], out [2000000], in [2000000], out [2000000], hlt
+    let program = vec![3, 2000000, 4, 2000000, 3, 2000000, 4, 2000000, 99];
+    let mut machine = Machine::with_memory(Box::new(PagedMemory::new(program)));
+    machine.push_input(7);
+    assert_eq!(machine.execute_program(), RunState::NeedsInput);
+
+    let mut restored = save_and_load(&machine, "paged");
+    assert_eq!(restored.read_memory(2000000), 7);
+    assert_eq!(restored.pop_output(), Some(7));
+
+    restored.push_input(9);
+    assert_eq!(restored.execute_program(), RunState::Halted);
+    assert_eq!(restored.pop_output(), Some(9));
+    assert_eq!(restored.cycles(), 4);
+}
+
+#[test]
+fn halted_machines_stay_halted() {
+    let mut machine = Machine::new(vec![99]);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert!(machine.snapshot().halted);
+
+    let mut restored = save_and_load(&machine, "halted");
+    restored.set_profile(Profile::new());
+    assert_eq!(restored.execute_program(), RunState::Halted);
+    assert_eq!(restored.profile().unwrap().total(), 0);
+}
+
+#[test]
+fn settings_are_not_part_of_a_snapshot() {
+    // add #1, #0, [1000], hlt
+    let mut machine = Machine::new(vec![1101, 1, 0, 1000, 99]);
+    machine.set_memory_limit(Some(100));
+    machine.set_budget(Some(10));
+
+    let mut restored = save_and_load(&machine, "settings");
+    assert_eq!(restored.budget(), None);
+    assert_eq!(restored.execute_program(), RunState::Halted);
+    assert_eq!(restored.read_memory(1000), 1);
+}
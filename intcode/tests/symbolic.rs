@@ -0,0 +1,169 @@
+use intcode::assembler::assemble;
+use intcode::symbolic::{Expr, Symbolic, SymbolicError};
+use intcode::MachineError;
+
+mod common;
+
+use common::day_input;
+
+fn day2() -> Symbolic {
+    let mut symbolic = Symbolic::new(&day_input(2));
+    symbolic.add_symbol(1, "noun", 0..=99);
+    symbolic.add_symbol(2, "verb", 0..=99);
+    symbolic
+}
+
+#[test]
+fn day2_output_is_linear_in_noun_and_verb() {
+    let symbolic = day2();
+    let expr = symbolic.evaluate(0).unwrap();
+
+    assert_eq!(expr.unknowns(), vec![0, 1]);
+    assert_eq!(expr.coefficient(&[1]), 1);
+    assert_eq!(expr.coefficient(&[0, 1]), 0);
+    assert_eq!(expr.evaluate(&[12, 2]), Some(4330636));
+    assert!(symbolic.check(&[12, 2], 0, 4330636));
+}
+
+#[test]
+fn day2_solves_for_the_target() {
+    assert_eq!(day2().solve(0, 19690720), Some(vec![60, 86]));
+    assert_eq!(day2().solve(0, -1), None);
+}
+
+#[test]
+fn readme_example_as_an_expression() {
+    let mut symbolic = Symbolic::new(&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+    symbolic.add_symbol(9, "a", 0..=99);
+    symbolic.add_symbol(10, "b", 0..=99);
+
+    let expr = symbolic.evaluate(0).unwrap();
+    assert_eq!(expr.format(&symbolic.names()), "50*a + 50*b");
+    assert_eq!(expr.evaluate(&[30, 40]), Some(3500));
+    assert_eq!(symbolic.solve(0, 3500), Some(vec![0, 70]));
+    assert_eq!(symbolic.solve(0, 3501), None);
+}
+
+#[test]
+fn readme_example_as_day2_noun_and_verb() {
+    // The noun and verb are themselves addresses here, so this runs the
+    // program from address 0 for every pair.
+    let mut symbolic = Symbolic::new(&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+    symbolic.add_symbol(1, "noun", 0..=11);
+    symbolic.add_symbol(2, "verb", 0..=11);
+
+    assert_eq!(symbolic.solve(0, 3500), Some(vec![9, 10]));
+}
+
+#[test]
+fn day2_programs_halt_anywhere() {
+    // add [noun], [verb], [0], hlt, followed by cells that are not code
+    let mut symbolic = Symbolic::new(&[1, 0, 0, 0, 99, 7, 7, 7]);
+    symbolic.add_symbol(1, "noun", 0..=7);
+    symbolic.add_symbol(2, "verb", 0..=7);
+
+    assert_eq!(symbolic.solve(0, 14), Some(vec![2, 7]));
+    assert_eq!(symbolic.solve(0, 100), Some(vec![0, 4]));
+    assert_eq!(symbolic.solve(0, 200), None);
+}
+
+#[test]
+fn expressions_format_signs_and_powers() {
+    let names = vec!["x".to_string(), "y".to_string()];
+    let x = Expr::unknown(0);
+    let y = Expr::unknown(1);
+    let expr = x
+        .multiply(&x)
+        .unwrap()
+        .add(&y.multiply(&Expr::constant(-3)).unwrap())
+        .unwrap()
+        .add(&Expr::constant(-7))
+        .unwrap();
+
+    assert_eq!(expr.format(&names), "x*x - 3*y - 7");
+    assert_eq!(Expr::default().format(&names), "0");
+    assert_eq!(expr.evaluate(&[4, 2]), Some(3));
+}
+
+#[test]
+fn nonlinear_expressions_are_enumerated() {
+    // mul [a], [a], [0], mul [0], [b], [0], hlt
+    let mut symbolic = Symbolic::new(&[2, 9, 9, 0, 2, 0, 10, 0, 99, 0, 0]);
+    symbolic.add_symbol(9, "a", -5..=5);
+    symbolic.add_symbol(10, "b", 0..=3);
+
+    assert_eq!(
+        symbolic.evaluate(0).unwrap().format(&symbolic.names()),
+        "a*a*b"
+    );
+    assert_eq!(symbolic.solve(0, 75), Some(vec![-5, 3]));
+}
+
+#[test]
+fn unconstrained_unknowns_try_every_value() {
+    // mul [x], #K, [z], mul [y], [x], [0], hlt, where K * x overflows for
+    // x below -10 and y = 0 leaves the output at 0 for any x
+    let scale = i64::MAX / 10;
+    let mut symbolic = Symbolic::new(&[1002, 10, scale, 11, 2, 9, 10, 0, 99, 0, 0, 0]);
+    symbolic.add_symbol(9, "y", 0..=0);
+    symbolic.add_symbol(10, "x", -20..=20);
+
+    assert_eq!(
+        symbolic.evaluate(0).unwrap().format(&symbolic.names()),
+        "y*x"
+    );
+    assert_eq!(symbolic.solve(0, 0), Some(vec![0, -10]));
+}
+
+const BRANCH: &str = "
+        jz [x], #zero
+        add #10, #0, [result]
+        hlt
+zero:   add #20, #0, [result]
+        hlt
+x:      db 0
+result: db 0
+";
+
+#[test]
+fn symbolic_branches_fall_back_to_search() {
+    let program = assemble(BRANCH).unwrap();
+    let mut symbolic = Symbolic::new(&program);
+    symbolic.add_symbol(13, "x", 0..=5);
+
+    assert_eq!(
+        symbolic.evaluate(14),
+        Err(SymbolicError::SymbolicControl { ip: 0 })
+    );
+    assert_eq!(symbolic.solve(14, 10), Some(vec![1]));
+    assert_eq!(symbolic.solve(14, 20), Some(vec![0]));
+    assert_eq!(symbolic.solve(14, 30), None);
+}
+
+#[test]
+fn writes_through_symbolic_addresses_are_rejected() {
+    // add #1, #0, [x], hlt
+    let mut symbolic = Symbolic::new(&[1101, 1, 0, 5, 99, 0]);
+    symbolic.add_symbol(3, "x", 5..=5);
+
+    assert_eq!(
+        symbolic.evaluate(5),
+        Err(SymbolicError::SymbolicAddress { ip: 0 })
+    );
+    assert_eq!(symbolic.solve(5, 1), Some(vec![5]));
+}
+
+#[test]
+fn writes_past_the_memory_limit_fall_back_to_search() {
+    // add #0, #0, [4000000000000000000], hlt
+    let symbolic = Symbolic::new(&[1101, 0, 0, 4_000_000_000_000_000_000, 99]);
+
+    assert_eq!(
+        symbolic.evaluate(0),
+        Err(SymbolicError::Fault(MachineError::MemoryLimitExceeded {
+            ip: 0,
+            address: 4_000_000_000_000_000_000,
+        }))
+    );
+    assert_eq!(symbolic.solve(0, 5), None);
+}
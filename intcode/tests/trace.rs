@@ -0,0 +1,220 @@
+use std::fs;
+use std::path::PathBuf;
+
+use intcode::trace::{
+    find_divergence, read_trace, Divergence, Filter, Operand, TraceRecord, Tracer,
+};
+use intcode::{Machine, MachineError, MemoryWrite, RunState};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("intcode-trace-{}-{}", name, std::process::id()))
+}
+
+/// Runs `program` `runs` times under a tracer and reads the trace back.
+fn trace_runs(
+    name: &str,
+    program: &[i64],
+    inputs: &[i64],
+    runs: usize,
+) -> (RunState, Vec<TraceRecord>) {
+    let path = temp_path(name);
+    let mut machine = Machine::new(program.to_vec());
+    for input in inputs {
+        machine.push_input(*input);
+    }
+    machine.set_tracer(Tracer::create(path.to_str().unwrap()).unwrap());
+
+    let mut state = machine.execute_program();
+    for _ in 1..runs {
+        state = machine.execute_program();
+    }
+    machine.take_tracer().unwrap().finish().unwrap();
+
+    let records = read_trace(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    (state, records)
+}
+
+fn trace(name: &str, program: &[i64], inputs: &[i64]) -> (RunState, Vec<TraceRecord>) {
+    trace_runs(name, program, inputs, 1)
+}
+
+// in [7], mul [7], #2, [7], out [7], hlt, x
+const DOUBLE: [i64; 8] = [3, 7, 1002, 7, 2, 7, 99, 0];
+
+#[test]
+fn records_operands_and_writes() {
+    let (state, records) = trace("records", &DOUBLE, &[21]);
+
+    assert_eq!(state, RunState::Halted);
+    assert_eq!(
+        records,
+        vec![
+            TraceRecord {
+                step: 0,
+                ip: 0,
+                opcode: 3,
+                operands: vec![Operand {
+                    address: 7,
+                    value: 0
+                }],
+                write: Some(MemoryWrite {
+                    address: 7,
+                    old_value: 0,
+                    value: 21
+                }),
+                relative_base: 0,
+                fault: None,
+            },
+            TraceRecord {
+                step: 1,
+                ip: 2,
+                opcode: 1002,
+                operands: vec![
+                    Operand {
+                        address: 7,
+                        value: 21
+                    },
+                    Operand {
+                        address: 4,
+                        value: 2
+                    },
+                    Operand {
+                        address: 7,
+                        value: 21
+                    },
+                ],
+                write: Some(MemoryWrite {
+                    address: 7,
+                    old_value: 21,
+                    value: 42
+                }),
+                relative_base: 0,
+                fault: None,
+            },
+            TraceRecord {
+                step: 2,
+                ip: 6,
+                opcode: 99,
+                operands: Vec::new(),
+                write: None,
+                relative_base: 0,
+                fault: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn faulting_instructions_are_recorded() {
+    // add [0], [0], [-1]
+    let (state, records) = trace("write-fault", &[1, 0, 0, -1], &[]);
+    assert_eq!(
+        state,
+        RunState::Faulted(MachineError::NegativeAddress { ip: 0, address: -1 })
+    );
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].opcode, 1);
+    assert_eq!(records[0].operands.len(), 2);
+    assert_eq!(records[0].write, None);
+    assert_eq!(
+        records[0].fault,
+        Some(MachineError::NegativeAddress { ip: 0, address: -1 }.to_string())
+    );
+
+    let (_, records) = trace("decode-fault", &[42], &[]);
+    assert_eq!(records.len(), 1);
+    assert_eq!((records[0].ip, records[0].opcode), (0, 42));
+    assert_eq!(
+        records[0].fault.as_deref(),
+        Some("unknown operation 42 at ip 0")
+    );
+}
+
+#[test]
+fn halt_is_recorded_once() {
+    let (state, records) = trace_runs("halt", &[99], &[], 3);
+    assert_eq!(state, RunState::Halted);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].opcode, 99);
+}
+
+#[test]
+fn filters_by_ip_op_address_and_step() {
+    let (_, records) = trace("filter", &DOUBLE, &[21]);
+    let steps = |filter: Filter| -> Vec<u64> {
+        records
+            .iter()
+            .filter(|record| filter.matches(record))
+            .map(|record| record.step)
+            .collect()
+    };
+
+    assert_eq!(steps(Filter::default()), vec![0, 1, 2]);
+    assert_eq!(
+        steps(Filter {
+            ip: Some(2),
+            ..Filter::default()
+        }),
+        vec![1]
+    );
+    assert_eq!(
+        steps(Filter {
+            op: Some(2),
+            ..Filter::default()
+        }),
+        vec![1]
+    );
+    assert_eq!(
+        steps(Filter {
+            address: Some(7),
+            ..Filter::default()
+        }),
+        vec![0, 1]
+    );
+    assert_eq!(
+        steps(Filter {
+            from: Some(1),
+            to: Some(1),
+            ..Filter::default()
+        }),
+        vec![1]
+    );
+}
+
+#[test]
+fn finds_where_traces_diverge() {
+    let (_, lhs) = trace("lhs", &DOUBLE, &[21]);
+    let (_, rhs) = trace("rhs", &DOUBLE, &[20]);
+
+    assert_eq!(find_divergence(&lhs, &lhs), None);
+    assert_eq!(
+        find_divergence(&lhs, &rhs),
+        Some(Divergence::Record(lhs[0].clone(), rhs[0].clone()))
+    );
+    assert_eq!(
+        find_divergence(&lhs[..2], &lhs),
+        Some(Divergence::Ended {
+            step: 2,
+            longer: lhs[2].clone()
+        })
+    );
+}
+
+#[test]
+fn reading_skips_blank_lines_and_rejects_bad_records() {
+    let path = temp_path("read");
+    let record = r#"{"step":0,"ip":0,"opcode":99,"operands":[],"relative_base":0}"#;
+
+    fs::write(&path, format!("\n{}\n\n", record)).unwrap();
+    let records = read_trace(path.to_str().unwrap()).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].write, None);
+    assert_eq!(records[0].fault, None);
+
+    fs::write(&path, "{\"step\":0}\n").unwrap();
+    assert!(read_trace(path.to_str().unwrap()).is_err());
+    fs::remove_file(&path).unwrap();
+
+    assert!(read_trace(path.to_str().unwrap()).is_err());
+}
@@ -0,0 +1,141 @@
+use std::num::Wrapping;
+
+use intcode::word::BigInt;
+use intcode::{Machine, MachineError, RunState};
+
+mod common;
+
+use common::{day_input, drive, joystick, robot, scripted, walk, Transcript};
+
+/// Runs `program` on every backend and checks they agree.
+fn compare<F: Fn() -> G, G: FnMut(&[i64]) -> Option<i64>>(
+    program: &[i64],
+    respond: F,
+) -> Transcript {
+    let checked = drive(Machine::<i64>::from_program(program), respond());
+    let wrapping = drive(Machine::<Wrapping<i64>>::from_program(program), respond());
+    let bigint = drive(Machine::<BigInt>::from_program(program), respond());
+
+    assert_eq!(wrapping, checked, "wrapping backend");
+    assert_eq!(bigint, checked, "bigint backend");
+    checked
+}
+
+#[test]
+fn day2_agrees_on_every_backend() {
+    let mut program = day_input(2);
+    program[1] = 12;
+    program[2] = 2;
+
+    let transcript = compare(&program, || scripted(&[]));
+    assert_eq!(transcript.state, RunState::Halted);
+}
+
+#[test]
+fn day5_agrees_on_every_backend() {
+    let program = day_input(5);
+
+    for input in [1, 5].iter() {
+        let transcript = compare(&program, || scripted(&[*input]));
+        assert_eq!(transcript.state, RunState::Halted);
+    }
+}
+
+#[test]
+fn day7_agrees_on_every_backend() {
+    let program = day_input(7);
+
+    for phase in 0..10 {
+        compare(&program, || scripted(&[phase, 0]));
+    }
+}
+
+#[test]
+fn day9_boost_passes_on_every_backend() {
+    let program = day_input(9);
+
+    let test_mode = compare(&program, || scripted(&[1]));
+    assert_eq!(test_mode.outputs, vec![2453265701]);
+    let sensor_boost = compare(&program, || scripted(&[2]));
+    assert_eq!(sensor_boost.outputs, vec![80805]);
+}
+
+#[test]
+fn day11_agrees_on_every_backend() {
+    let program = day_input(11);
+
+    for start in [0, 1].iter() {
+        let transcript = compare(&program, || robot(*start));
+        assert_eq!(transcript.state, RunState::Halted);
+    }
+}
+
+#[test]
+fn day13_agrees_on_every_backend() {
+    let mut program = day_input(13);
+    program[0] = 2;
+
+    let transcript = compare(&program, || joystick);
+    assert_eq!(transcript.state, RunState::Halted);
+}
+
+#[test]
+fn day15_agrees_on_every_backend() {
+    compare(&day_input(15), walk);
+}
+
+// add #max #1 [0], out [0], hlt
+const ADD_OVERFLOW: [i64; 7] = [1101, i64::MAX, 1, 0, 4, 0, 99];
+// mul #min #-1 [0], out [0], hlt
+const MULTIPLY_OVERFLOW: [i64; 7] = [1102, i64::MIN, -1, 0, 4, 0, 99];
+
+#[test]
+fn checked_words_fault_on_overflow() {
+    for program in [ADD_OVERFLOW, MULTIPLY_OVERFLOW].iter() {
+        let mut machine = Machine::new(program.to_vec());
+
+        assert_eq!(
+            machine.execute_program(),
+            RunState::Faulted(MachineError::ArithmeticOverflow { ip: 0 })
+        );
+        assert_eq!(machine.read_memory(0), program[0]);
+    }
+}
+
+#[test]
+fn wrapping_words_wrap_around() {
+    let mut machine = Machine::<Wrapping<i64>>::from_program(&ADD_OVERFLOW);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(machine.pop_output(), Some(Wrapping(i64::MIN)));
+
+    let mut machine = Machine::<Wrapping<i64>>::from_program(&MULTIPLY_OVERFLOW);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(machine.pop_output(), Some(Wrapping(i64::MIN)));
+}
+
+#[test]
+fn bigint_words_do_not_overflow() {
+    let mut machine = Machine::<BigInt>::from_program(&ADD_OVERFLOW);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(
+        machine.pop_output(),
+        Some(BigInt::from(i64::MAX) + BigInt::from(1))
+    );
+
+    let mut machine = Machine::<BigInt>::from_program(&MULTIPLY_OVERFLOW);
+    assert_eq!(machine.execute_program(), RunState::Halted);
+    assert_eq!(machine.pop_output(), Some(-BigInt::from(i64::MIN)));
+}
+
+#[test]
+fn bigint_jump_targets_must_fit_in_i64() {
+    // mul #2^62 #4 [11], jnz [11] [11], hlt
+    let program = [1102, 1 << 62, 4, 11, 5, 11, 11, 99, 0, 0, 0, 0];
+    let mut machine = Machine::<BigInt>::from_program(&program);
+
+    assert_eq!(
+        machine.execute_program(),
+        RunState::Faulted(MachineError::WordOutOfRange { ip: 4 })
+    );
+    assert_eq!(machine.read_memory(11), BigInt::from(1u128 << 64));
+}